@@ -0,0 +1,109 @@
+use std::{
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    backtracker::Observer,
+    render::{self, Highlight},
+    solver::TentsAndTreesConfig,
+};
+
+/// An observer that redraws the board in the terminal at every step of the search, so that tents
+/// can be seen being placed and removed
+pub struct Animation<W: Write> {
+    out: W,
+    delay: Duration,
+    nodes: usize,
+}
+
+impl Animation<io::Stdout> {
+    /// Create an animation on stdout, pausing for `delay` after every frame
+    pub fn new(delay: Duration) -> Self {
+        Animation::with_writer(io::stdout(), delay)
+    }
+}
+
+impl<W: Write> Animation<W> {
+    pub fn with_writer(mut out: W, delay: Duration) -> Self {
+        // Errors writing frames are ignored, as they only affect what is shown
+        let _ = write!(out, "{}", render::CLEAR_SCREEN);
+        Animation {
+            out,
+            delay,
+            nodes: 0,
+        }
+    }
+
+    fn draw(&mut self, config: &TentsAndTreesConfig, depth: usize, highlight: Highlight) {
+        let last_tent = config.last_tent();
+        let status = match (last_tent, highlight) {
+            (None, _) => "Starting position".to_string(),
+            (Some((row, col)), Highlight::Added) => format!("Placed tent at ({}, {})", row, col),
+            (Some((row, col)), Highlight::Removed) => {
+                format!("Removing tent at ({}, {})", row, col)
+            }
+        };
+        let board = render::render_board(&config.board, |row, col| {
+            (last_tent == Some((row, col))).then_some(highlight)
+        });
+        let _ = write!(
+            self.out,
+            "{}{}\n{}\nDepth: {:<4} Nodes: {}\n",
+            render::REDRAW,
+            board,
+            status,
+            depth,
+            self.nodes
+        );
+        let _ = self.out.flush();
+        thread::sleep(self.delay);
+    }
+}
+
+impl<W: Write> Observer<TentsAndTreesConfig> for Animation<W> {
+    fn on_enter(&mut self, config: &TentsAndTreesConfig, depth: usize) {
+        self.nodes += 1;
+        self.draw(config, depth, Highlight::Added);
+    }
+
+    fn on_backtrack(&mut self, config: &TentsAndTreesConfig, depth: usize) {
+        // The initial config is never undone, so there is nothing to show
+        if depth > 0 {
+            self.draw(config, depth, Highlight::Removed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backtracker::{self, SearchLimits, TranspositionTable},
+        board::{Board, CellType},
+    };
+
+    #[test]
+    fn frames_show_each_step() {
+        let board = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown],
+            ],
+            vec![Some(0), Some(1)],
+            vec![Some(1), Some(0)],
+        );
+        let mut animation = Animation::with_writer(Vec::new(), Duration::ZERO);
+        backtracker::solve_observed(
+            TentsAndTreesConfig::new(&board),
+            &SearchLimits::default(),
+            &mut TranspositionTable::default(),
+            &mut animation,
+        );
+        let output = String::from_utf8(animation.out).unwrap();
+        assert!(output.contains("Starting position"));
+        assert!(output.contains("Placed tent at (0, 1)"));
+        assert!(output.contains("Depth: 1    Nodes: 2"));
+    }
+}
@@ -1,32 +1,779 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::rng::Rng;
+
 pub trait Config: Sized {
-    /// Get all the successive configs from the current config
-    fn successors(&self) -> Vec<Self>;
+    /// A step from one config to the next. Moves should be cheap to create, as every move is
+    /// generated up front but only turned into a config when the search reaches it.
+    type Move;
+
+    /// Get all the moves that lead to the successive configs of the current config
+    fn moves(&self) -> Vec<Self::Move>;
+
+    /// Create the successive config reached by making the move from the current config
+    fn apply(&self, mv: &Self::Move) -> Self;
+
+    /// Lazily get all the successive configs from the current config
+    fn successors(&self) -> impl Iterator<Item = Self> {
+        self.moves().into_iter().map(|mv| self.apply(&mv))
+    }
 
     /// Check if the config is valid
     fn is_valid(&self) -> bool;
 
     /// Check if the config is the final/goal config. It must also be valid
     fn is_goal(&self) -> bool;
+
+    /// Get a hash identifying the state of the config. Two configs with the same key must lead to
+    /// the same result when searched, so that a failed search of one can be reused for the other.
+    /// Configs returning `None` are never looked up in the transposition table.
+    fn state_key(&self) -> Option<u64> {
+        None
+    }
+
+    /// Get the number of cells or values the config deduced by propagation when it was created,
+    /// on top of the move that created it
+    fn propagations(&self) -> usize {
+        0
+    }
+
+    /// Randomise any choices the config makes itself, such as which variable to branch on next,
+    /// for a randomised search. Configs whose choices are fixed do nothing.
+    fn randomise(&mut self, _rng: &mut Rng) {}
+}
+
+/// A config that can be searched by changing a single config in place, undoing each move when
+/// the search backtracks, instead of creating a new config for every successor
+pub trait UndoConfig: Config {
+    /// Make the move, changing the config in place into its successor
+    fn apply_mut(&mut self, mv: &Self::Move);
+
+    /// Undo the most recent move made with `apply_mut` that has not already been undone
+    fn undo(&mut self);
+}
+
+/// Callbacks made while a search runs, so that it can be logged, profiled or visualised. Every
+/// method does nothing by default, so observers only implement the events they need.
+pub trait Observer<C> {
+    /// Called when the search enters a valid config, before checking if it is the goal
+    fn on_enter(&mut self, _config: &C, _depth: usize) {}
+
+    /// Called when a successor config at the given depth fails `Config::is_valid`
+    fn on_reject(&mut self, _config: &C, _depth: usize) {}
+
+    /// Called when the search leaves a config that was fully searched without finding a goal
+    fn on_backtrack(&mut self, _config: &C, _depth: usize) {}
+
+    /// Called when the search finds a goal config
+    fn on_goal(&mut self, _config: &C, _depth: usize) {}
+}
+
+/// The unit observer ignores every event
+impl<C> Observer<C> for () {}
+
+/// Hit, miss and storage counts for a `TranspositionTable`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TableStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub stores: usize,
+    pub evictions: usize,
+}
+
+/// A bounded cache of the state keys of configs that are known to have no solution. Each key maps
+/// to a single slot, and a new key replaces whatever was in its slot before.
+pub struct TranspositionTable {
+    slots: Vec<Option<u64>>,
+    stats: TableStats,
+}
+
+impl TranspositionTable {
+    /// The number of slots used by `TranspositionTable::default`
+    pub const DEFAULT_CAPACITY: usize = 1 << 16;
+
+    /// Create a table holding up to `capacity` keys, rounded up to a power of two
+    pub fn new(capacity: usize) -> Self {
+        TranspositionTable {
+            slots: vec![None; capacity.max(1).next_power_of_two()],
+            stats: TableStats::default(),
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key as usize) & (self.slots.len() - 1)
+    }
+
+    /// Check if the key has been stored as a dead state, updating the hit and miss counts
+    pub fn contains(&mut self, key: u64) -> bool {
+        if self.slots[self.slot(key)] == Some(key) {
+            self.stats.hits += 1;
+            true
+        } else {
+            self.stats.misses += 1;
+            false
+        }
+    }
+
+    /// Record the key as a dead state
+    pub fn insert(&mut self, key: u64) {
+        let slot = self.slot(key);
+        match self.slots[slot] {
+            Some(old) if old != key => self.stats.evictions += 1,
+            _ => {}
+        }
+        self.slots[slot] = Some(key);
+        self.stats.stores += 1;
+    }
+
+    pub fn stats(&self) -> TableStats {
+        self.stats
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(TranspositionTable::DEFAULT_CAPACITY)
+    }
+}
+
+/// Limits on how much work a search may do before giving up
+#[derive(Debug, Default, Clone)]
+pub struct SearchLimits {
+    /// Give up once this time has passed
+    pub deadline: Option<Instant>,
+    /// Give up after expanding this many configs
+    pub max_nodes: Option<usize>,
+    /// Give up once this flag is set, e.g. from another thread
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl SearchLimits {
+    /// Set the deadline to the given duration from now
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Check if the search should stop, given the number of configs expanded so far
+    fn exceeded(&self, nodes: usize) -> Option<GiveUpReason> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            return Some(GiveUpReason::Cancelled);
+        }
+        if self.max_nodes.is_some_and(|max_nodes| nodes >= max_nodes) {
+            return Some(GiveUpReason::NodeLimit);
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Some(GiveUpReason::Timeout);
+        }
+        None
+    }
+}
+
+/// The limit that stopped a search before it finished
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GiveUpReason {
+    Timeout,
+    NodeLimit,
+    Cancelled,
+}
+
+impl Display for GiveUpReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GiveUpReason::Timeout => write!(f, "the time limit was reached"),
+            GiveUpReason::NodeLimit => write!(f, "the node limit was reached"),
+            GiveUpReason::Cancelled => write!(f, "the search was cancelled"),
+        }
+    }
+}
+
+/// The result of a search
+#[derive(Debug)]
+pub enum Outcome<C> {
+    /// A goal config was found
+    Solved(C),
+    /// The whole search space was explored without finding a goal
+    Unsolvable,
+    /// The search stopped early, so there may or may not be a solution
+    GaveUp(GiveUpReason),
+}
+
+/// Statistics on the work done during a search
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SearchReport {
+    /// Configs whose successors were generated
+    pub nodes_expanded: usize,
+    /// Successor configs that failed `Config::is_valid`
+    pub rejected: usize,
+    /// The deepest level reached, with the initial config at depth 0
+    pub max_depth: usize,
+    /// Configs that were fully searched without finding a goal
+    pub backtracks: usize,
+    /// The total of `Config::propagations` over every config searched
+    pub propagations: usize,
+    /// The number of times a randomised search was restarted
+    pub restarts: usize,
+    pub elapsed: Duration,
+    pub table: TableStats,
+}
+
+impl SearchReport {
+    /// Add the work done by a later run of a restarted search
+    fn merge(&mut self, run: &SearchReport) {
+        self.nodes_expanded += run.nodes_expanded;
+        self.rejected += run.rejected;
+        self.max_depth = self.max_depth.max(run.max_depth);
+        self.backtracks += run.backtracks;
+        self.propagations += run.propagations;
+        self.table.hits += run.table.hits;
+        self.table.misses += run.table.misses;
+        self.table.stores += run.table.stores;
+        self.table.evictions += run.table.evictions;
+    }
+}
+
+impl Display for SearchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Nodes expanded:   {}", self.nodes_expanded)?;
+        writeln!(f, "Configs rejected: {}", self.rejected)?;
+        writeln!(f, "Max depth:        {}", self.max_depth)?;
+        writeln!(f, "Backtracks:       {}", self.backtracks)?;
+        writeln!(f, "Propagations:     {}", self.propagations)?;
+        writeln!(f, "Restarts:         {}", self.restarts)?;
+        writeln!(
+            f,
+            "Table:            {} hits, {} misses, {} stores, {} evictions",
+            self.table.hits, self.table.misses, self.table.stores, self.table.evictions
+        )?;
+        write!(f, "Elapsed:          {:.3?}", self.elapsed)
+    }
+}
+
+/// The outcome of a search along with the statistics of how it got there
+#[derive(Debug)]
+pub struct SearchResult<C> {
+    pub outcome: Outcome<C>,
+    pub report: SearchReport,
 }
 
 /// Solve the backtracking problem using the specified config (recursive)
-pub fn solve<C: Config>(config: C) -> Option<C> {
-    // Return once we find the goal
-    if config.is_goal() {
-        return Some(config);
+pub fn solve<C: Config>(config: C, limits: &SearchLimits) -> SearchResult<C> {
+    solve_with_table(config, limits, &mut TranspositionTable::default())
+}
+
+/// Solve the backtracking problem using the specified config (recursive), skipping any config
+/// whose state is already recorded as dead in the table and recording every newly failed one
+pub fn solve_with_table<C: Config>(
+    config: C,
+    limits: &SearchLimits,
+    table: &mut TranspositionTable,
+) -> SearchResult<C> {
+    solve_observed(config, limits, table, &mut ())
+}
+
+/// Solve the backtracking problem using the specified config (recursive), reporting every step
+/// of the search to the observer
+pub fn solve_observed<C: Config, O: Observer<C>>(
+    config: C,
+    limits: &SearchLimits,
+    table: &mut TranspositionTable,
+    observer: &mut O,
+) -> SearchResult<C> {
+    Search::new(limits, table, observer).run(config)
+}
+
+/// Solve the backtracking problem using the specified config (recursive), trying moves in a
+/// random order. The same seed always gives the same search.
+pub fn solve_shuffled<C: Config>(
+    mut config: C,
+    limits: &SearchLimits,
+    seed: u64,
+) -> SearchResult<C> {
+    let mut rng = Rng::new(seed);
+    config.randomise(&mut rng);
+    Search::new(limits, &mut TranspositionTable::default(), &mut ())
+        .with_rng(rng)
+        .run(config)
+}
+
+/// How a randomised search is restarted when it runs too long
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestartPolicy {
+    /// The number of nodes the first run may expand before restarting
+    pub initial_nodes: usize,
+    /// How many times more nodes each run may expand than the one before
+    pub growth: usize,
+    /// The number of restarts, after which the last run continues until it finishes
+    pub max_restarts: usize,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            initial_nodes: 100,
+            growth: 2,
+            max_restarts: 10,
+        }
     }
+}
 
-    // Search the successors for the goal
-    for child in config.successors() {
-        // The config must be valid for it to be the goal
-        if child.is_valid() {
-            // Check if the config leads to a solution
-            match solve(child) {
-                Some(solution) => return Some(solution),
-                None => continue,
+/// Solve the backtracking problem using the specified config, with randomised searches that are
+/// restarted with a new order whenever they expand too many nodes. The node limit grows with every
+/// restart, so a search that keeps being unlucky still finishes eventually. Dead states are
+/// shared between runs, and the limits apply to all the runs together. The same seed always
+/// gives the same search.
+pub fn solve_with_restarts<C: Config + Clone>(
+    config: C,
+    limits: &SearchLimits,
+    seed: u64,
+    policy: &RestartPolicy,
+) -> SearchResult<C> {
+    let start = Instant::now();
+    let mut seeds = Rng::new(seed);
+    let table = &mut TranspositionTable::default();
+    let mut report = SearchReport::default();
+    let mut run_nodes = policy.initial_nodes;
+    loop {
+        let mut run_limits = limits.clone();
+        let remaining = limits
+            .max_nodes
+            .map(|max_nodes| max_nodes.saturating_sub(report.nodes_expanded));
+        let restart_allowed = report.restarts < policy.max_restarts
+            && remaining.is_none_or(|nodes| nodes > run_nodes);
+        if restart_allowed {
+            run_limits.max_nodes = Some(run_nodes);
+        } else {
+            run_limits.max_nodes = remaining;
+        }
+
+        let mut rng = Rng::new(seeds.next_u64());
+        let mut run_config = config.clone();
+        run_config.randomise(&mut rng);
+        let run = Search::new(&run_limits, table, &mut ())
+            .with_rng(rng)
+            .run(run_config);
+        report.merge(&run.report);
+
+        match run.outcome {
+            Outcome::GaveUp(GiveUpReason::NodeLimit) if restart_allowed => {
+                report.restarts += 1;
+                run_nodes = run_nodes.saturating_mul(policy.growth);
+            }
+            outcome => {
+                report.elapsed = start.elapsed();
+                return SearchResult { outcome, report };
             }
         }
     }
-    // No solution found, prune this tree
-    None
+}
+
+/// Solve the backtracking problem by changing the specified config in place (recursive). Each
+/// move is undone when the search backtracks, so only one config exists throughout the search,
+/// and it is returned in its goal state if a solution is found.
+pub fn solve_in_place<C: UndoConfig>(config: C, limits: &SearchLimits) -> SearchResult<C> {
+    solve_in_place_observed(config, limits, &mut TranspositionTable::default(), &mut ())
+}
+
+/// Solve the backtracking problem by changing the specified config in place (recursive),
+/// reporting every step of the search to the observer
+pub fn solve_in_place_observed<C: UndoConfig, O: Observer<C>>(
+    config: C,
+    limits: &SearchLimits,
+    table: &mut TranspositionTable,
+    observer: &mut O,
+) -> SearchResult<C> {
+    Search::new(limits, table, observer).run_in_place(config)
+}
+
+/// The state shared by every level of a recursive search
+struct Search<'a, O> {
+    limits: &'a SearchLimits,
+    table: &'a mut TranspositionTable,
+    observer: &'a mut O,
+    report: SearchReport,
+    start: Instant,
+    table_before: TableStats,
+    // Shuffles the moves of every config, if the search is randomised
+    rng: Option<Rng>,
+}
+
+impl<'a, O> Search<'a, O> {
+    fn new(
+        limits: &'a SearchLimits,
+        table: &'a mut TranspositionTable,
+        observer: &'a mut O,
+    ) -> Self {
+        Search {
+            limits,
+            table_before: table.stats(),
+            table,
+            observer,
+            report: SearchReport::default(),
+            start: Instant::now(),
+            rng: None,
+        }
+    }
+
+    /// Get the moves of the config, in a random order if the search is randomised
+    fn moves<C: Config>(&mut self, config: &C) -> Vec<C::Move> {
+        let mut moves = config.moves();
+        if let Some(rng) = self.rng.as_mut() {
+            rng.shuffle(&mut moves);
+        }
+        moves
+    }
+
+    fn with_rng(mut self, rng: Rng) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Search from the initial config, returning the outcome and the report
+    fn run<C: Config>(mut self, config: C) -> SearchResult<C>
+    where
+        O: Observer<C>,
+    {
+        self.report.propagations += config.propagations();
+        let outcome = match self.solve(config, 0) {
+            Ok(Some(solution)) => Outcome::Solved(solution),
+            Ok(None) => Outcome::Unsolvable,
+            Err(reason) => Outcome::GaveUp(reason),
+        };
+        self.finish(outcome)
+    }
+
+    /// Search from the initial config in place, returning the outcome and the report
+    fn run_in_place<C: UndoConfig>(mut self, mut config: C) -> SearchResult<C>
+    where
+        O: Observer<C>,
+    {
+        self.report.propagations += config.propagations();
+        let outcome = match self.solve_in_place(&mut config, 0) {
+            Ok(true) => Outcome::Solved(config),
+            Ok(false) => Outcome::Unsolvable,
+            Err(reason) => Outcome::GaveUp(reason),
+        };
+        self.finish(outcome)
+    }
+
+    /// Complete the report with the table usage and time taken during this search
+    fn finish<C>(self, outcome: Outcome<C>) -> SearchResult<C> {
+        let mut report = self.report;
+        let table_after = self.table.stats();
+        report.table = TableStats {
+            hits: table_after.hits - self.table_before.hits,
+            misses: table_after.misses - self.table_before.misses,
+            stores: table_after.stores - self.table_before.stores,
+            evictions: table_after.evictions - self.table_before.evictions,
+        };
+        report.elapsed = self.start.elapsed();
+        SearchResult { outcome, report }
+    }
+
+    fn solve<C: Config>(&mut self, config: C, depth: usize) -> Result<Option<C>, GiveUpReason>
+    where
+        O: Observer<C>,
+    {
+        self.report.max_depth = self.report.max_depth.max(depth);
+        self.observer.on_enter(&config, depth);
+        // Return once we find the goal
+        if config.is_goal() {
+            self.observer.on_goal(&config, depth);
+            return Ok(Some(config));
+        }
+
+        if let Some(reason) = self.limits.exceeded(self.report.nodes_expanded) {
+            return Err(reason);
+        }
+        self.report.nodes_expanded += 1;
+
+        // Search the successors for the goal
+        for mv in self.moves(&config) {
+            let child = config.apply(&mv);
+            // The config must be valid for it to be the goal
+            if !child.is_valid() {
+                self.observer.on_reject(&child, depth + 1);
+                self.report.rejected += 1;
+                continue;
+            }
+            // Skip the config if an identical one has already been searched without success
+            let key = child.state_key();
+            if key.is_some_and(|key| self.table.contains(key)) {
+                continue;
+            }
+            self.report.propagations += child.propagations();
+            // Check if the config leads to a solution. Giving up is passed straight back up,
+            // as the config has not been fully searched and must not be recorded as dead.
+            match self.solve(child, depth + 1)? {
+                Some(solution) => return Ok(Some(solution)),
+                None => {
+                    if let Some(key) = key {
+                        self.table.insert(key);
+                    }
+                }
+            }
+        }
+        // No solution found, prune this tree
+        self.observer.on_backtrack(&config, depth);
+        self.report.backtracks += 1;
+        Ok(None)
+    }
+
+    /// The same search as `solve`, but making and undoing moves on a single config. Returns
+    /// whether the config was left in a goal state.
+    fn solve_in_place<C: UndoConfig>(
+        &mut self,
+        config: &mut C,
+        depth: usize,
+    ) -> Result<bool, GiveUpReason>
+    where
+        O: Observer<C>,
+    {
+        self.report.max_depth = self.report.max_depth.max(depth);
+        self.observer.on_enter(config, depth);
+        // Return once we find the goal
+        if config.is_goal() {
+            self.observer.on_goal(config, depth);
+            return Ok(true);
+        }
+
+        if let Some(reason) = self.limits.exceeded(self.report.nodes_expanded) {
+            return Err(reason);
+        }
+        self.report.nodes_expanded += 1;
+
+        // Search the successors for the goal
+        for mv in self.moves(config) {
+            config.apply_mut(&mv);
+            // The config must be valid for it to be the goal
+            if !config.is_valid() {
+                self.observer.on_reject(config, depth + 1);
+                self.report.rejected += 1;
+                config.undo();
+                continue;
+            }
+            // Skip the config if an identical one has already been searched without success
+            let key = config.state_key();
+            if key.is_some_and(|key| self.table.contains(key)) {
+                config.undo();
+                continue;
+            }
+            self.report.propagations += config.propagations();
+            // Leave the config as it is when a goal is found or the search gives up
+            if self.solve_in_place(config, depth + 1)? {
+                return Ok(true);
+            }
+            if let Some(key) = key {
+                self.table.insert(key);
+            }
+            config.undo();
+        }
+        // No solution found, prune this tree
+        self.observer.on_backtrack(config, depth);
+        self.report.backtracks += 1;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    /// Counts down from a number, never reaching a goal
+    #[derive(Clone)]
+    struct Countdown(usize);
+
+    impl Config for Countdown {
+        type Move = usize;
+
+        fn moves(&self) -> Vec<usize> {
+            (0..self.0).collect()
+        }
+
+        fn apply(&self, mv: &usize) -> Self {
+            Countdown(*mv)
+        }
+
+        fn is_valid(&self) -> bool {
+            true
+        }
+
+        fn is_goal(&self) -> bool {
+            false
+        }
+    }
+
+    /// Reaches the goal after its first move, counting how many successors are created
+    struct FirstMoveWins {
+        applied: Rc<Cell<usize>>,
+        done: bool,
+    }
+
+    impl Config for FirstMoveWins {
+        type Move = ();
+
+        fn moves(&self) -> Vec<()> {
+            vec![(); 10]
+        }
+
+        fn apply(&self, _mv: &()) -> Self {
+            self.applied.set(self.applied.get() + 1);
+            FirstMoveWins {
+                applied: Rc::clone(&self.applied),
+                done: true,
+            }
+        }
+
+        fn is_valid(&self) -> bool {
+            true
+        }
+
+        fn is_goal(&self) -> bool {
+            self.done
+        }
+    }
+
+    #[test]
+    fn successors_are_created_lazily() {
+        let applied = Rc::new(Cell::new(0));
+        let config = FirstMoveWins {
+            applied: Rc::clone(&applied),
+            done: false,
+        };
+        let outcome = solve(config, &SearchLimits::default()).outcome;
+        assert!(matches!(outcome, Outcome::Solved(_)));
+        assert_eq!(applied.get(), 1);
+    }
+
+    /// Records every event as a string
+    #[derive(Default)]
+    struct EventLog(Vec<String>);
+
+    impl Observer<Countdown> for EventLog {
+        fn on_enter(&mut self, config: &Countdown, depth: usize) {
+            self.0.push(format!("enter {} at {}", config.0, depth));
+        }
+
+        fn on_backtrack(&mut self, config: &Countdown, depth: usize) {
+            self.0.push(format!("backtrack {} at {}", config.0, depth));
+        }
+    }
+
+    #[test]
+    fn observer_sees_every_step() {
+        let mut log = EventLog::default();
+        let table = &mut TranspositionTable::default();
+        solve_observed(Countdown(2), &SearchLimits::default(), table, &mut log);
+        assert_eq!(
+            log.0,
+            [
+                "enter 2 at 0",
+                "enter 0 at 1",
+                "backtrack 0 at 1",
+                "enter 1 at 1",
+                "enter 0 at 2",
+                "backtrack 0 at 2",
+                "backtrack 1 at 1",
+                "backtrack 2 at 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn exhausted_search_is_unsolvable() {
+        let result = solve(Countdown(3), &SearchLimits::default());
+        assert!(matches!(result.outcome, Outcome::Unsolvable));
+        // Every count n is followed by all the counts below it, so 2^n configs are expanded
+        assert_eq!(result.report.nodes_expanded, 8);
+        assert_eq!(result.report.backtracks, 8);
+        assert_eq!(result.report.max_depth, 3);
+        assert_eq!(result.report.rejected, 0);
+    }
+
+    #[test]
+    fn search_gives_up_at_limits() {
+        let limits = SearchLimits::default().with_max_nodes(100);
+        let result = solve(Countdown(20), &limits);
+        assert!(matches!(
+            result.outcome,
+            Outcome::GaveUp(GiveUpReason::NodeLimit)
+        ));
+        assert_eq!(result.report.nodes_expanded, 100);
+
+        let limits = SearchLimits::default().with_timeout(Duration::ZERO);
+        let outcome = solve(Countdown(20), &limits).outcome;
+        assert!(matches!(outcome, Outcome::GaveUp(GiveUpReason::Timeout)));
+
+        let cancel = Arc::new(AtomicBool::new(true));
+        let limits = SearchLimits::default().with_cancel_flag(cancel);
+        let outcome = solve(Countdown(20), &limits).outcome;
+        assert!(matches!(outcome, Outcome::GaveUp(GiveUpReason::Cancelled)));
+    }
+
+    #[test]
+    fn restarts_grow_until_the_search_finishes() {
+        let policy = RestartPolicy {
+            initial_nodes: 4,
+            growth: 2,
+            max_restarts: 3,
+        };
+        let result = solve_with_restarts(Countdown(8), &SearchLimits::default(), 1, &policy);
+        assert!(matches!(result.outcome, Outcome::Unsolvable));
+        assert_eq!(result.report.restarts, 3);
+
+        let limits = SearchLimits::default().with_max_nodes(30);
+        let result = solve_with_restarts(Countdown(8), &limits, 1, &policy);
+        assert!(matches!(
+            result.outcome,
+            Outcome::GaveUp(GiveUpReason::NodeLimit)
+        ));
+        assert_eq!(result.report.nodes_expanded, 30);
+    }
+
+    #[test]
+    fn table_remembers_dead_states() {
+        let mut table = TranspositionTable::new(4);
+        assert!(!table.contains(7));
+        table.insert(7);
+        assert!(table.contains(7));
+        // 11 shares a slot with 7, so it replaces it
+        table.insert(11);
+        assert!(!table.contains(7));
+        assert!(table.contains(11));
+        assert_eq!(
+            table.stats(),
+            TableStats {
+                hits: 2,
+                misses: 2,
+                stores: 2,
+                evictions: 1,
+            }
+        );
+    }
 }
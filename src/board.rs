@@ -1,9 +1,9 @@
 use std::{
-    cmp::{max, min},
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display, Write},
 };
 
-use crate::AppError;
+use crate::{render, rng::split_mix, AppError};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CellType {
@@ -11,6 +11,9 @@ pub enum CellType {
     Empty,
     Tent,
     Tree,
+    /// Outside the playing area, for boards with holes or an irregular outline. Blocked cells are
+    /// never tents or trees, and are not counted towards clues.
+    Blocked,
 }
 
 #[derive(PartialEq, Clone)]
@@ -34,17 +37,140 @@ impl Clue {
      */
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Direction {
     North,
     South,
     East,
     West,
+    /// Only used on hex grids, where each row is drawn half a cell to the right of the one above
+    NorthEast,
+    /// Only used on hex grids
+    SouthWest,
+}
+
+impl Direction {
+    /// The directions on a square grid, in the order they are checked: West, North, East, South
+    pub const SQUARE: [Direction; 4] = [
+        Direction::West,
+        Direction::North,
+        Direction::East,
+        Direction::South,
+    ];
+
+    /// The directions on a hex grid, in the order they are checked, going clockwise from West.
+    /// North and South lead to the cells up and to the left, and down and to the right.
+    pub const HEX: [Direction; 6] = [
+        Direction::West,
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::South,
+        Direction::SouthWest,
+    ];
+
+    /// Get the (row, column) offset of a step in this direction
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (-1, 0),
+            Direction::South => (1, 0),
+            Direction::East => (0, 1),
+            Direction::West => (0, -1),
+            Direction::NorthEast => (-1, 1),
+            Direction::SouthWest => (1, -1),
+        }
+    }
+}
+
+/// The shape of the cells of a board
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Grid {
+    /// Square cells, with four neighbours each
+    #[default]
+    Square,
+    /// Hexagonal cells, with six neighbours each. Cells are stored in rows and columns as axial
+    /// coordinates, so the board is a rhombus with every row shifted half a cell to the right of
+    /// the one above. Clues run along the rows, the columns and the diagonals.
+    Hex,
+}
+
+impl Grid {
+    /// Get the directions to the neighbours of a cell
+    pub fn directions(&self) -> &'static [Direction] {
+        match self {
+            Grid::Square => &Direction::SQUARE,
+            Grid::Hex => &Direction::HEX,
+        }
+    }
+
+    /// Get the axes that clues run along
+    pub fn axes(&self) -> &'static [Axis] {
+        match self {
+            Grid::Square => &[Axis::Row, Axis::Column],
+            Grid::Hex => &[Axis::Row, Axis::Column, Axis::Diagonal],
+        }
+    }
 }
 
+/// A way of dividing the board into lines of cells, each of which can have a clue for the number
+/// of tents in it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    Row,
+    Column,
+    /// The lines running from the top right to the bottom left of a hex board, numbered from the
+    /// top left corner. Diagonal `n` holds the cells whose row and column add up to `n`.
+    Diagonal,
+    /// Outlined regions of any shape, as given by the board's region map. Cells outside every
+    /// region are not on any line along this axis.
+    Region,
+}
+
+impl Axis {
+    const ALL: [Axis; 4] = [Axis::Row, Axis::Column, Axis::Diagonal, Axis::Region];
+}
+
+impl Display for Axis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Axis::Row => write!(f, "row"),
+            Axis::Column => write!(f, "column"),
+            Axis::Diagonal => write!(f, "diagonal"),
+            Axis::Region => write!(f, "region"),
+        }
+    }
+}
+
+/// The rules that a puzzle is played with, where puzzle sources differ
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RuleSet {
+    /// Whether tents may touch diagonally. Tents may never touch orthogonally.
+    pub diagonal_tents_allowed: bool,
+    /// Whether the left and right edges of the board are joined, so that cells on one edge
+    /// neighbour the cells on the other
+    pub wrap_horizontal: bool,
+    /// Whether the top and bottom edges of the board are joined
+    pub wrap_vertical: bool,
+    pub grid: Grid,
+}
+
+/// Offsets to the cells sharing only a corner with a cell
+const DIAGONAL_OFFSETS: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+/// The most regions a board can have, as puzzle files name them with the letters `a` to `z`
+pub const MAX_REGIONS: usize = 26;
+
 #[derive(Debug)]
 pub enum BoardErrorType {
     NoTreeFound(usize, usize),
     ImpossibleTentPosition(usize, usize),
+    TouchingTents(usize, usize),
+    TooManyTents(Axis, usize),
+    NoTreeForTent(usize, usize),
+    InvalidPuzzle(usize),
+    IncorrectSolution(usize),
+    /// A region index with no clue, or past the last region that can be written as a letter
+    InvalidRegion(usize),
 }
 
 impl Display for BoardErrorType {
@@ -62,17 +188,196 @@ impl Display for BoardErrorType {
                 "{}",
                 format_args!("Impossible for tent to be at position ({}, {})", r, c)
             ),
+            BoardErrorType::TouchingTents(r, c) => {
+                write!(
+                    f,
+                    "The tent at position ({}, {}) touches another tent",
+                    r, c
+                )
+            }
+            BoardErrorType::TooManyTents(axis, line) => {
+                write!(
+                    f,
+                    "There are more tents than the clue allows in {} {}",
+                    axis, line
+                )
+            }
+            BoardErrorType::NoTreeForTent(r, c) => write!(
+                f,
+                "There is no tree left to pair with the tent at position ({}, {})",
+                r, c
+            ),
+            BoardErrorType::InvalidPuzzle(count) => {
+                write!(f, "The puzzle has {} problems", count)
+            }
+            BoardErrorType::IncorrectSolution(count) => {
+                write!(f, "The solution has {} mistakes", count)
+            }
+            BoardErrorType::InvalidRegion(region) => write!(
+                f,
+                "Region {} does not have a clue or is past the {} regions a board can have",
+                region, MAX_REGIONS
+            ),
+        }
+    }
+}
+
+/// A problem that stops a board from being a valid puzzle, as found by `Board::validate`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// The board has a different number of rows to the row clues
+    WrongRowCount { rows: usize, expected: usize },
+    /// A row has a different number of cells to the column clues
+    JaggedRow {
+        row: usize,
+        len: usize,
+        expected: usize,
+    },
+    /// Two given tents touch each other
+    TouchingTents((usize, usize), (usize, usize)),
+    /// A given tent cannot be paired with a tree of its own
+    TentWithoutTree(usize, usize),
+    /// A tree has nowhere next to it for its tent
+    TreeWithoutRoom(usize, usize),
+    /// A clue asks for more tents than the line has cells
+    ClueTooLarge {
+        axis: Axis,
+        line: usize,
+        clue: usize,
+        cells: usize,
+    },
+    /// A line already has more given tents than its clue
+    TooManyTents {
+        axis: Axis,
+        line: usize,
+        clue: usize,
+    },
+    /// The clues along two axes add up to different numbers of tents
+    ClueSumsDisagree {
+        axis: Axis,
+        sum: usize,
+        other_axis: Axis,
+        other_sum: usize,
+    },
+    /// The clues along an axis do not add up to the number of trees
+    ClueSumNotTreeCount {
+        axis: Axis,
+        sum: usize,
+        trees: usize,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::WrongRowCount { rows, expected } => {
+                write!(f, "The board has {} rows but {} row clues", rows, expected)
+            }
+            Violation::JaggedRow { row, len, expected } => write!(
+                f,
+                "Row {} has {} cells but there are {} column clues",
+                row, len, expected
+            ),
+            Violation::TouchingTents((r1, c1), (r2, c2)) => write!(
+                f,
+                "The tents at ({}, {}) and ({}, {}) touch",
+                r1, c1, r2, c2
+            ),
+            Violation::TentWithoutTree(r, c) => {
+                write!(f, "The tent at ({}, {}) has no tree of its own", r, c)
+            }
+            Violation::TreeWithoutRoom(r, c) => {
+                write!(f, "The tree at ({}, {}) has nowhere for its tent", r, c)
+            }
+            Violation::ClueTooLarge {
+                axis,
+                line,
+                clue,
+                cells,
+            } => write!(
+                f,
+                "The clue {} for {} {} is larger than its {} cells",
+                clue, axis, line, cells
+            ),
+            Violation::TooManyTents { axis, line, clue } => write!(
+                f,
+                "There are more tents in {} {} than its clue {}",
+                axis, line, clue
+            ),
+            Violation::ClueSumsDisagree {
+                axis,
+                sum,
+                other_axis,
+                other_sum,
+            } => write!(
+                f,
+                "The {} clues add up to {} but the {} clues add up to {}",
+                axis, sum, other_axis, other_sum
+            ),
+            Violation::ClueSumNotTreeCount { axis, sum, trees } => write!(
+                f,
+                "The {} clues add up to {} but there are {} trees",
+                axis, sum, trees
+            ),
+        }
+    }
+}
+
+/// Running totals of the cell types in a single row or column of the board
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LineCounts {
+    pub tents: usize,
+    pub empty: usize,
+    pub unknown: usize,
+}
+
+impl LineCounts {
+    fn add(&mut self, cell: CellType) {
+        match cell {
+            CellType::Tent => self.tents += 1,
+            CellType::Empty => self.empty += 1,
+            CellType::Unknown => self.unknown += 1,
+            CellType::Tree | CellType::Blocked => {}
+        }
+    }
+
+    fn remove(&mut self, cell: CellType) {
+        match cell {
+            CellType::Tent => self.tents -= 1,
+            CellType::Empty => self.empty -= 1,
+            CellType::Unknown => self.unknown -= 1,
+            CellType::Tree | CellType::Blocked => {}
         }
     }
 }
 
 #[derive(Default, Clone)]
 pub struct Board {
-    pub board: Vec<Vec<CellType>>,
+    board: Vec<Vec<CellType>>,
     pub col_clues: Vec<Clue>,
     pub row_clues: Vec<Clue>,
+    /// The clues for the diagonals of a hex board, which is empty for square boards
+    pub diagonal_clues: Vec<Clue>,
+    /// The clues for the regions, which is empty unless the board has a region map
+    pub region_clues: Vec<Clue>,
+    // The region each cell belongs to, if the board has a region map
+    regions: Vec<Vec<Option<usize>>>,
     pub col_count: usize,
     pub row_count: usize,
+    pub rules: RuleSet,
+    // Counts for each line along each axis, indexed by `Axis`, kept up to date by `set_celltype`.
+    // The diagonals are counted on every board, so that the grid can be changed at any time.
+    totals: [Vec<LineCounts>; 4],
+    // Zobrist hash of the cells, kept up to date by `set_celltype`
+    hash: u64,
+    // The previous contents of every changed cell, oldest first, while journaling is enabled
+    journal: Option<Vec<(usize, usize, CellType)>>,
+}
+
+/// Get the Zobrist key for a cell type at a position. The keys are derived by mixing the position
+/// and cell type, so no table of random numbers needs to be stored.
+fn zobrist_key(row: usize, col: usize, cell: CellType) -> u64 {
+    split_mix((row as u64) << 35 | (col as u64) << 3 | cell as u64)
 }
 
 impl Board {
@@ -81,8 +386,25 @@ impl Board {
         col_clues: Vec<Option<usize>>,
         row_clues: Vec<Option<usize>>,
     ) -> Self {
-        let row_count = col_clues.len();
-        let col_count = row_clues.len();
+        let row_count = row_clues.len();
+        let col_count = col_clues.len();
+
+        let width = board.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut totals = [
+            vec![LineCounts::default(); board.len()],
+            vec![LineCounts::default(); width],
+            vec![LineCounts::default(); (board.len() + width).saturating_sub(1)],
+            Vec::new(),
+        ];
+        let mut hash = 0;
+        for (row_idx, row) in board.iter().enumerate() {
+            for (col_idx, cell) in row.iter().enumerate() {
+                totals[Axis::Row as usize][row_idx].add(*cell);
+                totals[Axis::Column as usize][col_idx].add(*cell);
+                totals[Axis::Diagonal as usize][row_idx + col_idx].add(*cell);
+                hash ^= zobrist_key(row_idx, col_idx, *cell);
+            }
+        }
 
         Board {
             board,
@@ -100,146 +422,466 @@ impl Board {
                     //is_complete: clue.is_none(),
                 })
                 .collect(),
+            diagonal_clues: Vec::new(),
+            region_clues: Vec::new(),
+            regions: Vec::new(),
             col_count,
             row_count,
+            rules: RuleSet::default(),
+            totals,
+            hash,
+            journal: None,
+        }
+    }
+
+    /// Set the clues for the diagonals of a hex board, numbered as in `Axis::Diagonal`
+    pub fn set_diagonal_clues(&mut self, clues: Vec<Option<usize>>) {
+        self.diagonal_clues = clues.into_iter().map(|clue| Clue { clue }).collect();
+    }
+
+    /// Divide the board into regions, each with an optional clue for the number of tents in it.
+    /// The map gives the index of the region each cell belongs to, which must have a clue in
+    /// `clues`, or `None` for cells outside every region. There can be at most `MAX_REGIONS`
+    /// regions.
+    pub fn set_regions(
+        &mut self,
+        regions: Vec<Vec<Option<usize>>>,
+        clues: Vec<Option<usize>>,
+    ) -> Result<(), AppError> {
+        let mut totals = vec![LineCounts::default(); clues.len()];
+        for (row, region_row) in regions.iter().enumerate() {
+            for (col, region) in region_row.iter().enumerate() {
+                if let Some(region) = *region {
+                    if region >= clues.len().min(MAX_REGIONS) {
+                        return Err(AppError::BoardError(BoardErrorType::InvalidRegion(region)));
+                    }
+                    totals[region].add(self.board[row][col]);
+                }
+            }
         }
+        self.regions = regions;
+        self.region_clues = clues.into_iter().map(|clue| Clue { clue }).collect();
+        self.totals[Axis::Region as usize] = totals;
+        Ok(())
+    }
+
+    /// Get the region a cell belongs to, if it is in one
+    pub fn region(&self, row: usize, col: usize) -> Option<usize> {
+        self.regions
+            .get(row)
+            .and_then(|regions| regions.get(col).copied().flatten())
     }
 
     pub fn get_celltype(&self, row: usize, col: usize) -> CellType {
         self.board[row][col]
     }
 
-    pub fn verify_setup(&self) -> bool {
-        let row_counts = self.board.iter().all(|row| row.len() == self.row_count);
-        let col_counts = self.board.len() == self.col_count;
-        row_counts & col_counts
+    /// Set the cell at the given position, keeping the row and column counts up to date
+    pub fn set_celltype(&mut self, row: usize, col: usize, cell: CellType) {
+        let old = self.board[row][col];
+        if old == cell {
+            return;
+        }
+        if let Some(journal) = self.journal.as_mut() {
+            journal.push((row, col, old));
+        }
+        self.write_cell(row, col, old, cell);
     }
 
-    pub fn is_complete(&self) -> bool {
-        // self.col_clues.iter().all(|c| c.is_complete())
-        //     & self.row_clues.iter().all(|r| r.is_complete())
-        self.col_clues.iter().enumerate().all(|(col_idx, clue)| {
-            if let Some(clue_val) = clue.clue {
-                clue_val
-                    == self
-                        .get_column(col_idx)
-                        .iter()
-                        .filter(|val| *val == &CellType::Tent)
-                        .count()
-            } else {
-                true
-            }
-        }) & self.row_clues.iter().enumerate().all(|(row_idx, clue)| {
-            if let Some(clue_val) = clue.clue {
-                clue_val
-                    == self.board[row_idx]
-                        .iter()
-                        .filter(|val| *val == &CellType::Tent)
-                        .count()
-            } else {
-                true
+    fn write_cell(&mut self, row: usize, col: usize, old: CellType, cell: CellType) {
+        for axis in Axis::ALL {
+            if let Some(line) = self.line(axis, row, col) {
+                let counts = &mut self.totals[axis as usize][line];
+                counts.remove(old);
+                counts.add(cell);
             }
-        })
+        }
+        self.hash ^= zobrist_key(row, col, old) ^ zobrist_key(row, col, cell);
+        self.board[row][col] = cell;
     }
 
-    pub fn get_column(&self, col: usize) -> Vec<CellType> {
-        self.board.iter().map(|row| row[col]).collect()
+    /// Start recording every cell change, so that changes can be undone with `rollback`
+    pub fn enable_journal(&mut self) {
+        if self.journal.is_none() {
+            self.journal = Some(Vec::new());
+        }
     }
 
-    pub fn does_surrounding_have_tent(&self, row: usize, col: usize) -> bool {
-        // Check West
-        if col > 0 && self.board[row][col - 1] == CellType::Tent {
-            return true;
-        }
-        // Check North
-        if row > 0 && self.board[row - 1][col] == CellType::Tent {
-            return true;
+    /// Get a marker for the current state of the board, to pass to `rollback` later. Changes are
+    /// only recorded once `enable_journal` has been called.
+    pub fn checkpoint(&self) -> usize {
+        self.journal.as_ref().map_or(0, |journal| journal.len())
+    }
+
+    /// Undo every cell change made since the checkpoint was taken
+    pub fn rollback(&mut self, checkpoint: usize) {
+        while self.checkpoint() > checkpoint {
+            let Some((row, col, old)) = self.journal.as_mut().and_then(|journal| journal.pop())
+            else {
+                break;
+            };
+            let cell = self.board[row][col];
+            self.write_cell(row, col, cell, old);
         }
-        // Check East
-        if col < self.col_count - 1 && self.board[row][col + 1] == CellType::Tent {
-            return true;
+    }
+
+    /// Get the Zobrist hash of the cells on the board. Boards with the same cells have the same
+    /// hash, however they were reached.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Get the running counts of tents, empty and unknown cells in a row
+    pub fn row_counts(&self, row: usize) -> LineCounts {
+        self.line_counts(Axis::Row, row)
+    }
+
+    /// Get the running counts of tents, empty and unknown cells in a column
+    pub fn col_counts(&self, col: usize) -> LineCounts {
+        self.line_counts(Axis::Column, col)
+    }
+
+    /// Get the running counts of tents, empty and unknown cells in a line along any axis
+    pub fn line_counts(&self, axis: Axis, line: usize) -> LineCounts {
+        self.totals[axis as usize][line]
+    }
+
+    /// Get the axes that the clues of this board run along: those of its grid, and the regions
+    /// if it has any
+    pub fn axes(&self) -> impl Iterator<Item = Axis> + '_ {
+        let regions = (!self.region_clues.is_empty()).then_some(Axis::Region);
+        self.rules.grid.axes().iter().copied().chain(regions)
+    }
+
+    /// Get the index of the line along an axis that holds the given cell, if there is one
+    pub fn line(&self, axis: Axis, row: usize, col: usize) -> Option<usize> {
+        match axis {
+            Axis::Row => Some(row),
+            Axis::Column => Some(col),
+            Axis::Diagonal => Some(row + col),
+            Axis::Region => self.region(row, col),
         }
-        // Check South
-        if row < self.row_count - 1 && self.board[row + 1][col] == CellType::Tent {
-            return true;
+    }
+
+    /// Get the clues for every line along an axis
+    pub fn clues(&self, axis: Axis) -> &[Clue] {
+        match axis {
+            Axis::Row => &self.row_clues,
+            Axis::Column => &self.col_clues,
+            Axis::Diagonal => &self.diagonal_clues,
+            Axis::Region => &self.region_clues,
         }
-        false
     }
 
-    pub fn is_valid_peek(&self, row: usize, col: usize) -> bool {
-        let mut board_copy = self.board.clone();
-        board_copy[row][col] = CellType::Tent;
-        let row_min = max(0i32, row as i32 - 1) as usize;
-        let row_max = min(row + 1, self.row_count - 1);
-        let col_min = max(0i32, col as i32 - 1) as usize;
-        let col_max = min(col + 1, self.col_count - 1);
-        for r in row_min..=row_max {
-            for c in col_min..=col_max {
-                // If we are in the position of the last added tent, then don't check
-                if r == row && c == col {
+    /// Set or clear the clue for a line
+    pub fn set_clue(&mut self, axis: Axis, line: usize, clue: Option<usize>) {
+        let clues = match axis {
+            Axis::Row => &mut self.row_clues,
+            Axis::Column => &mut self.col_clues,
+            Axis::Diagonal => &mut self.diagonal_clues,
+            Axis::Region => &mut self.region_clues,
+        };
+        clues[line] = Clue { clue };
+    }
+
+    /// Get the clue for a line, or `None` if it has no clue
+    pub fn clue(&self, axis: Axis, line: usize) -> Option<usize> {
+        self.clues(axis).get(line).and_then(Clue::get_clue)
+    }
+
+    /// Get the positions of the cells in a line along an axis
+    pub fn line_cells(&self, axis: Axis, line: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.row_count)
+            .flat_map(move |row| (0..self.col_count).map(move |col| (row, col)))
+            .filter(move |(row, col)| self.line(axis, *row, *col) == Some(line))
+    }
+
+    /// Check whether the line through a cell along every axis has room for another tent
+    fn lines_have_room(&self, row: usize, col: usize) -> bool {
+        self.axes().all(|axis| {
+            self.line(axis, row, col).is_none_or(|line| {
+                self.clue(axis, line)
+                    .is_none_or(|clue_val| self.line_counts(axis, line).tents < clue_val)
+            })
+        })
+    }
+
+    /// Check the board for every problem that stops it from being a valid puzzle. If the rows
+    /// do not match the clues, only those problems are reported, as the other checks rely on
+    /// the board's shape.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let width = self.col_clues.len();
+        if self.board.len() != self.row_clues.len() {
+            violations.push(Violation::WrongRowCount {
+                rows: self.board.len(),
+                expected: self.row_clues.len(),
+            });
+        }
+        for (row, cells) in self.board.iter().enumerate() {
+            if cells.len() != width {
+                violations.push(Violation::JaggedRow {
+                    row,
+                    len: cells.len(),
+                    expected: width,
+                });
+            }
+        }
+        if !violations.is_empty() {
+            return violations;
+        }
+
+        let tents = self.get_tent_positions();
+        for (row, col) in &tents {
+            for other in self.tent_neighbours(*row, *col) {
+                if self.board[other.0][other.1] == CellType::Tent && (*row, *col) < other {
+                    violations.push(Violation::TouchingTents((*row, *col), other));
+                }
+            }
+        }
+        for ((row, col), tree) in tents.iter().zip(self.pair_tents(&tents)) {
+            if tree.is_none() {
+                violations.push(Violation::TentWithoutTree(*row, *col));
+            }
+        }
+        let trees = self.get_tree_positions();
+        for (row, col, _) in &trees {
+            let has_room = self.rules.grid.directions().iter().any(|direction| {
+                self.neighbour(*row, *col, direction).is_some_and(|(r, c)| {
+                    matches!(self.board[r][c], CellType::Unknown | CellType::Tent)
+                })
+            });
+            if !has_room {
+                violations.push(Violation::TreeWithoutRoom(*row, *col));
+            }
+        }
+
+        for axis in self.axes() {
+            for (line, counts) in self.totals[axis as usize].iter().enumerate() {
+                let Some(clue) = self.clue(axis, line) else {
                     continue;
+                };
+                let cells = counts.tents + counts.empty + counts.unknown;
+                if clue > cells {
+                    violations.push(Violation::ClueTooLarge {
+                        axis,
+                        line,
+                        clue,
+                        cells,
+                    });
+                } else if counts.tents > clue {
+                    violations.push(Violation::TooManyTents { axis, line, clue });
                 }
-                if board_copy[r][c] == CellType::Tent {
-                    return false;
+            }
+        }
+
+        // Each tree has one tent, so the clues along any axis that covers the whole board add up
+        // to the number of trees. Regions need not cover the whole board, so they are skipped.
+        let sums: Vec<(Axis, usize)> = self
+            .axes()
+            .filter(|axis| *axis != Axis::Region)
+            .filter_map(|axis| {
+                let clues: Option<Vec<usize>> =
+                    self.clues(axis).iter().map(Clue::get_clue).collect();
+                Some((axis, clues?.iter().sum()))
+            })
+            .collect();
+        for (idx, (axis, sum)) in sums.iter().enumerate() {
+            for (other_axis, other_sum) in &sums[idx + 1..] {
+                if sum != other_sum {
+                    violations.push(Violation::ClueSumsDisagree {
+                        axis: *axis,
+                        sum: *sum,
+                        other_axis: *other_axis,
+                        other_sum: *other_sum,
+                    });
                 }
             }
+            if *sum != trees.len() {
+                violations.push(Violation::ClueSumNotTreeCount {
+                    axis: *axis,
+                    sum: *sum,
+                    trees: trees.len(),
+                });
+            }
         }
+        violations
+    }
 
-        let row_counts = board_copy.iter().all(|row| row.len() == self.row_count);
-        let col_counts = board_copy.len() == self.col_count;
-        row_counts & col_counts
+    pub fn is_complete(&self) -> bool {
+        self.axes().all(|axis| {
+            self.clues(axis)
+                .iter()
+                .zip(self.totals[axis as usize].iter())
+                .all(|(clue, counts)| clue.clue.is_none_or(|clue_val| clue_val == counts.tents))
+        })
     }
 
-    fn does_surrounding_have_tree(&self, row: usize, col: usize) -> bool {
-        // Check West
-        if col > 0 && self.board[row][col - 1] == CellType::Tree {
-            return true;
-        }
-        // Check North
-        if row > 0 && self.board[row - 1][col] == CellType::Tree {
-            return true;
+    pub fn get_column(&self, col: usize) -> Vec<CellType> {
+        self.board.iter().map(|row| row[col]).collect()
+    }
+
+    /// Get the cell offset from the given one, if it is in the playing area. Steps off an edge
+    /// come back on the opposite edge when the rules wrap the board in that direction.
+    fn offset(&self, row: usize, col: usize, offset: (isize, isize)) -> Option<(usize, usize)> {
+        let r = wrap_index(row, offset.0, self.row_count, self.rules.wrap_vertical)?;
+        let c = wrap_index(col, offset.1, self.col_count, self.rules.wrap_horizontal)?;
+        // A board narrower than the step can wrap back onto the cell itself
+        ((r, c) != (row, col) && self.board[r][c] != CellType::Blocked).then_some((r, c))
+    }
+
+    /// Get the neighbouring cell in the given direction, if it is in the playing area
+    pub fn neighbour(
+        &self,
+        row: usize,
+        col: usize,
+        direction: &Direction,
+    ) -> Option<(usize, usize)> {
+        self.offset(row, col, direction.offset())
+    }
+
+    /// Check if any cell sharing an edge with the given one has the given type
+    fn is_next_to(&self, row: usize, col: usize, cell: CellType) -> bool {
+        self.rules.grid.directions().iter().any(|direction| {
+            self.neighbour(row, col, direction)
+                .is_some_and(|(r, c)| self.board[r][c] == cell)
+        })
+    }
+
+    /// Get the cells that cannot hold a tent while the given cell does: the cells sharing an edge
+    /// with it, and on square grids the cells touching it diagonally as well unless the rules
+    /// allow diagonal tents. Each cell is listed once, even where a narrow wrapped board brings
+    /// two offsets to the same cell.
+    pub fn tent_neighbours(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let diagonals: &[(isize, isize)] =
+            if self.rules.diagonal_tents_allowed || self.rules.grid == Grid::Hex {
+                &[]
+            } else {
+                &DIAGONAL_OFFSETS
+            };
+        let mut cells = Vec::new();
+        let offsets = self.rules.grid.directions().iter().map(Direction::offset);
+        for offset in offsets.chain(diagonals.iter().copied()) {
+            if let Some(cell) = self.offset(row, col, offset) {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
         }
-        // Check East
-        if col < self.col_count - 1 && self.board[row][col + 1] == CellType::Tree {
-            return true;
+        cells.into_iter()
+    }
+
+    /// Check if a tent at the given cell would touch another tent
+    pub fn has_touching_tent(&self, row: usize, col: usize) -> bool {
+        self.tent_neighbours(row, col)
+            .any(|(r, c)| self.board[r][c] == CellType::Tent)
+    }
+
+    /// Place a tent, then mark every unknown cell that can no longer hold a tent as empty: the
+    /// cells touching the tent, and the rest of each line through it once its clue is met.
+    /// Returns the number of cells marked as empty.
+    pub fn place_tent(&mut self, row: usize, col: usize) -> usize {
+        self.set_celltype(row, col, CellType::Tent);
+        let mut marked = 0;
+        let neighbours: Vec<(usize, usize)> = self.tent_neighbours(row, col).collect();
+        for (r, c) in neighbours {
+            if self.board[r][c] == CellType::Unknown {
+                self.set_celltype(r, c, CellType::Empty);
+                marked += 1;
+            }
         }
-        // Check South
-        if row < self.row_count - 1 && self.board[row + 1][col] == CellType::Tree {
-            return true;
+
+        let axes: Vec<Axis> = self.axes().collect();
+        for axis in axes {
+            let Some(line) = self.line(axis, row, col) else {
+                continue;
+            };
+            if self.clue(axis, line) == Some(self.line_counts(axis, line).tents) {
+                let cells: Vec<(usize, usize)> = self.line_cells(axis, line).collect();
+                for (r, c) in cells {
+                    if self.board[r][c] == CellType::Unknown {
+                        self.set_celltype(r, c, CellType::Empty);
+                        marked += 1;
+                    }
+                }
+            }
         }
-        false
+        marked
+    }
+
+    pub fn does_surrounding_have_tent(&self, row: usize, col: usize) -> bool {
+        self.is_next_to(row, col, CellType::Tent)
+    }
+
+    /// Check whether a tent could be placed at the given position without touching another tent
+    /// or going over the clues of the lines through it
+    pub fn is_valid_peek(&self, row: usize, col: usize) -> bool {
+        !self.has_touching_tent(row, col) && self.lines_have_room(row, col)
+    }
+
+    fn does_surrounding_have_tree(&self, row: usize, col: usize) -> bool {
+        self.is_next_to(row, col, CellType::Tree)
     }
 
     pub fn set_mandatory_empty(&mut self) {
         // Set all board values to CellType::Empty if the row_clue = 0
-        for (row_num, row_clue) in self.row_clues.iter().enumerate() {
-            if row_clue.clue.unwrap_or(1) == 0 {
-                self.board[row_num]
-                    .iter_mut()
-                    .filter(|x| x == &&CellType::Unknown)
-                    .for_each(|x| *x = CellType::Empty);
+        for row_num in 0..self.row_clues.len() {
+            if self.row_clues[row_num].clue.unwrap_or(1) == 0 {
+                for col_num in 0..self.board[row_num].len() {
+                    if self.board[row_num][col_num] == CellType::Unknown {
+                        self.set_celltype(row_num, col_num, CellType::Empty);
+                    }
+                }
             }
         }
 
         // Set all board values to CellType::Empty if the col_clue = 0
-        for (col_num, col_clue) in self.col_clues.iter().enumerate() {
-            if col_clue.clue.unwrap_or(1) == 0 {
-                //self.update_column(col_num, CellType::Empty);
-                for row in self.board.iter_mut() {
-                    if col_num < row.len() && row[col_num] == CellType::Unknown {
-                        row[col_num] = CellType::Empty;
+        for col_num in 0..self.col_clues.len() {
+            if self.col_clues[col_num].clue.unwrap_or(1) == 0 {
+                for row_num in 0..self.board.len() {
+                    if col_num < self.board[row_num].len()
+                        && self.board[row_num][col_num] == CellType::Unknown
+                    {
+                        self.set_celltype(row_num, col_num, CellType::Empty);
                     }
                 }
             }
         }
 
-        // Set all board values to CellType::Empty if there is no CellType::Tree to the North, South, East or West.
+        // Set all board values to CellType::Empty if the clue of a diagonal or region = 0
+        let axes: Vec<Axis> = self
+            .axes()
+            .filter(|axis| !matches!(axis, Axis::Row | Axis::Column))
+            .collect();
+        for axis in axes {
+            for line in 0..self.clues(axis).len() {
+                if self.clue(axis, line) == Some(0) {
+                    let cells: Vec<(usize, usize)> = self.line_cells(axis, line).collect();
+                    for (row, col) in cells {
+                        if self.board[row][col] == CellType::Unknown {
+                            self.set_celltype(row, col, CellType::Empty);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Mark the cells around the given tents, and the rest of any line they fill, as empty
+        for (row, col) in self.get_tent_positions() {
+            self.place_tent(row, col);
+        }
+
+        // Set all board values to CellType::Empty if there is no CellType::Tree next to them.
         for row in 0..self.row_count {
             for col in 0..self.col_count {
                 if self.board[row][col] == CellType::Unknown
                     && !self.does_surrounding_have_tree(row, col)
                 {
-                    self.board[row][col] = CellType::Empty;
+                    self.set_celltype(row, col, CellType::Empty);
                 }
             }
         }
@@ -262,28 +904,110 @@ impl Board {
         tree_positions
     }
 
-    fn get_possible_tent_directions(&self, tree_row: usize, tree_col: usize) -> Vec<Direction> {
-        let mut possible_directions = Vec::new();
-        // Check West
-        if tree_col > 0 && self.board[tree_row][tree_col - 1] == CellType::Unknown {
-            possible_directions.push(Direction::West);
-        }
-        // Check North
-        if tree_row > 0 && self.board[tree_row - 1][tree_col] == CellType::Unknown {
-            possible_directions.push(Direction::North);
+    pub fn get_tent_positions(&self) -> Vec<(usize, usize)> {
+        let mut tent_positions = Vec::new();
+        for (row_idx, row) in self.board.iter().enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                if value == &CellType::Tent {
+                    tent_positions.push((row_idx, col_idx));
+                }
+            }
         }
-        // Check East
-        if tree_col < self.col_count - 1 && self.board[tree_row][tree_col + 1] == CellType::Unknown
+        tent_positions
+    }
+
+    /// Check that the tents already on the board can be part of a solution: no two touch, no
+    /// line has more tents than its clue, and each can be paired with a different tree next to it
+    pub fn verify_given_tents(&self) -> Result<(), AppError> {
+        let tents = self.get_tent_positions();
+        if let Some((row, col)) = tents
+            .iter()
+            .find(|(row, col)| self.has_touching_tent(*row, *col))
         {
-            possible_directions.push(Direction::East);
+            return Err(AppError::BoardError(BoardErrorType::TouchingTents(
+                *row, *col,
+            )));
         }
-        // Check South
-        if tree_row < self.row_count - 1 && self.board[tree_row + 1][tree_col] == CellType::Unknown
-        {
-            possible_directions.push(Direction::South);
+        for axis in self.axes() {
+            for (line, counts) in self.totals[axis as usize].iter().enumerate() {
+                if self
+                    .clue(axis, line)
+                    .is_some_and(|clue| counts.tents > clue)
+                {
+                    return Err(AppError::BoardError(BoardErrorType::TooManyTents(
+                        axis, line,
+                    )));
+                }
+            }
+        }
+        let pairs = self.pair_tents(&tents);
+        if let Some(((row, col), _)) = tents.iter().zip(pairs).find(|(_, tree)| tree.is_none()) {
+            return Err(AppError::BoardError(BoardErrorType::NoTreeForTent(
+                *row, *col,
+            )));
+        }
+        Ok(())
+    }
+
+    /// Pair as many of the tents as possible with a different tree next to each of them, returning
+    /// the tree paired with each tent in turn, or `None` for the tents left without one
+    pub fn pair_tents(&self, tents: &[(usize, usize)]) -> Vec<Option<(usize, usize)>> {
+        // The tent that each tree is paired with, found by augmenting paths
+        let mut owners = HashMap::new();
+        for tent_idx in 0..tents.len() {
+            self.find_tree(tents, tent_idx, &mut owners, &mut HashSet::new());
+        }
+        let mut pairs = vec![None; tents.len()];
+        for (tree, tent_idx) in owners {
+            pairs[tent_idx] = Some(tree);
         }
+        pairs
+    }
 
-        possible_directions
+    /// Find a tree for a tent, taking it from the tent it is paired with if that tent can be
+    /// given another tree instead
+    fn find_tree(
+        &self,
+        tents: &[(usize, usize)],
+        tent_idx: usize,
+        owners: &mut HashMap<(usize, usize), usize>,
+        visited: &mut HashSet<(usize, usize)>,
+    ) -> bool {
+        let (row, col) = tents[tent_idx];
+        for direction in self.rules.grid.directions() {
+            let Some((r, c)) = self.neighbour(row, col, direction) else {
+                continue;
+            };
+            if self.board[r][c] != CellType::Tree || !visited.insert((r, c)) {
+                continue;
+            }
+            let free = match owners.get(&(r, c)).copied() {
+                None => true,
+                Some(other) => self.find_tree(tents, other, owners, visited),
+            };
+            if free {
+                owners.insert((r, c), tent_idx);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Get the directions from a tree to the unknown cells next to it. Where a narrow wrapped
+    /// board brings two directions to the same cell, only the first is given.
+    pub fn get_possible_tent_directions(&self, tree_row: usize, tree_col: usize) -> Vec<Direction> {
+        let mut cells = Vec::new();
+        let mut directions = Vec::new();
+        for direction in self.rules.grid.directions() {
+            let Some((r, c)) = self.neighbour(tree_row, tree_col, direction) else {
+                continue;
+            };
+            if self.board[r][c] == CellType::Unknown && !cells.contains(&(r, c)) {
+                cells.push((r, c));
+                directions.push(*direction);
+            }
+        }
+        directions
     }
 
     pub fn get_possible_tent_position(
@@ -299,46 +1023,32 @@ impl Board {
             )));
         }
         // Get the position of the possible tent, as long as the position is not out of bounds
-        let (tent_row, tent_col) = match direction {
-            Direction::North => {
-                if tree_row == 0 {
-                    return Err(AppError::BoardError(
-                        BoardErrorType::ImpossibleTentPosition(tree_row, tree_col),
-                    ));
-                }
-                (tree_row - 1, tree_col)
-            }
-            Direction::West => {
-                if tree_col == 0 {
-                    return Err(AppError::BoardError(
-                        BoardErrorType::ImpossibleTentPosition(tree_row, tree_col),
-                    ));
-                }
-                (tree_row, tree_col - 1)
-            }
-            Direction::South => {
-                if tree_row >= self.row_count - 1 {
-                    return Err(AppError::BoardError(
-                        BoardErrorType::ImpossibleTentPosition(tree_row, tree_col),
-                    ));
-                }
-                (tree_row + 1, tree_col)
-            }
-            Direction::East => {
-                if tree_col >= self.col_count - 1 {
-                    return Err(AppError::BoardError(
-                        BoardErrorType::ImpossibleTentPosition(tree_row, tree_col),
-                    ));
-                }
-                (tree_row, tree_col + 1)
-            }
-        };
-        Ok((tent_row, tent_col))
+        self.neighbour(tree_row, tree_col, direction)
+            .ok_or(AppError::BoardError(
+                BoardErrorType::ImpossibleTentPosition(tree_row, tree_col),
+            ))
+    }
+}
+
+/// Step from an index along a line of the given length, wrapping around the ends if `wrap` is set
+fn wrap_index(index: usize, step: isize, len: usize, wrap: bool) -> Option<usize> {
+    if wrap {
+        Some((index as isize + step).rem_euclid(len as isize) as usize)
+    } else {
+        index.checked_add_signed(step).filter(|index| *index < len)
     }
 }
 
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.rules.grid == Grid::Hex {
+            let layout = render::hex_layout(
+                self,
+                |row, col| render::cell_symbol(self.get_celltype(row, col)).to_string(),
+                |axis, line| render::clue_text(self, axis, line),
+            );
+            return f.write_str(&layout);
+        }
         f.write_str("   |")?;
         for clue in &self.col_clues {
             // let is_complete_marker = {
@@ -378,6 +1088,7 @@ impl Display for Board {
                     CellType::Unknown => " - ",
                     CellType::Tent => " X ",
                     CellType::Tree => " T ",
+                    CellType::Blocked => " # ",
                 };
                 f.write_str(ct_repr)?;
             }
@@ -401,8 +1112,8 @@ impl PartialEq for Board {
         {
             return false;
         }
-        for row in 0..self.col_count {
-            for col in 0..self.row_count {
+        for row in 0..self.row_count {
+            for col in 0..self.col_count {
                 if self.board[row][col] != other.board[row][col] {
                     return false;
                 }
@@ -413,3 +1124,306 @@ impl PartialEq for Board {
 }
 
 impl Eq for Board {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_counts_follow_cell_changes() {
+        let mut board = Board::new(
+            vec![
+                vec![CellType::Unknown, CellType::Tree, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown, CellType::Unknown],
+                vec![CellType::Tree, CellType::Unknown, CellType::Empty],
+            ],
+            vec![Some(1), None, Some(1)],
+            vec![Some(1), Some(1), None],
+        );
+        assert_eq!(
+            board.row_counts(0),
+            LineCounts {
+                tents: 0,
+                empty: 0,
+                unknown: 2
+            }
+        );
+        assert_eq!(board.col_counts(2).empty, 1);
+
+        board.set_celltype(0, 2, CellType::Tent);
+        board.set_celltype(1, 0, CellType::Tent);
+        board.set_celltype(0, 0, CellType::Empty);
+        assert_eq!(board.row_counts(0).tents, 1);
+        assert_eq!(board.row_counts(0).unknown, 0);
+        assert_eq!(board.col_counts(0).tents, 1);
+        assert_eq!(board.col_counts(0).empty, 1);
+        assert!(board.is_complete());
+
+        board.set_celltype(1, 0, CellType::Unknown);
+        assert_eq!(board.col_counts(0).tents, 0);
+        assert_eq!(board.row_counts(1).unknown, 3);
+        assert!(!board.is_complete());
+    }
+
+    #[test]
+    fn blocked_cells_are_outside_the_board() {
+        let board = Board::new(
+            vec![
+                vec![CellType::Blocked, CellType::Tree, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown, CellType::Blocked],
+                vec![CellType::Blocked, CellType::Blocked, CellType::Blocked],
+            ],
+            vec![None, None, None],
+            vec![None, None, None],
+        );
+        assert_eq!(
+            board.row_counts(0),
+            LineCounts {
+                tents: 0,
+                empty: 0,
+                unknown: 1
+            }
+        );
+        assert_eq!(board.col_counts(2).unknown, 1);
+        let neighbours: Vec<(usize, usize)> = board.tent_neighbours(1, 1).collect();
+        assert_eq!(neighbours, [(1, 0), (0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn hex_cells_have_six_neighbours() {
+        let mut board = Board::new(
+            vec![vec![CellType::Unknown; 3]; 3],
+            vec![None, None, None],
+            vec![None, None, None],
+        );
+        board.rules.grid = Grid::Hex;
+        let neighbours: Vec<(usize, usize)> = board.tent_neighbours(1, 1).collect();
+        assert_eq!(neighbours, [(1, 0), (0, 1), (0, 2), (1, 2), (2, 1), (2, 0)]);
+
+        board.set_celltype(0, 2, CellType::Tent);
+        board.set_celltype(2, 0, CellType::Tent);
+        assert_eq!(board.line_counts(Axis::Diagonal, 2).tents, 2);
+        assert_eq!(board.line_counts(Axis::Diagonal, 1).tents, 0);
+        assert!(board.has_touching_tent(1, 1));
+    }
+
+    #[test]
+    fn given_tents_must_fit_the_rules() {
+        let board = Board::new(
+            vec![
+                vec![CellType::Tent, CellType::Tree, CellType::Tent],
+                vec![CellType::Unknown, CellType::Unknown, CellType::Unknown],
+                vec![CellType::Tree, CellType::Unknown, CellType::Unknown],
+            ],
+            vec![None, None, None],
+            vec![None, None, None],
+        );
+        assert!(matches!(
+            board.verify_given_tents(),
+            Err(AppError::BoardError(BoardErrorType::NoTreeForTent(0, 2)))
+        ));
+        let mut board = board;
+        board.set_celltype(0, 2, CellType::Unknown);
+        board.set_celltype(1, 0, CellType::Tent);
+        assert!(matches!(
+            board.verify_given_tents(),
+            Err(AppError::BoardError(BoardErrorType::TouchingTents(0, 0)))
+        ));
+        board.set_celltype(1, 0, CellType::Unknown);
+        board.set_celltype(2, 1, CellType::Tent);
+        assert!(board.verify_given_tents().is_ok());
+        assert_eq!(
+            board.pair_tents(&[(0, 0), (2, 1)]),
+            [Some((0, 1)), Some((2, 0))]
+        );
+
+        board.row_clues[2] = Clue { clue: Some(0) };
+        assert!(matches!(
+            board.verify_given_tents(),
+            Err(AppError::BoardError(BoardErrorType::TooManyTents(
+                Axis::Row,
+                2
+            )))
+        ));
+    }
+
+    #[test]
+    fn validate_lists_every_problem() {
+        let board = Board::new(
+            vec![
+                vec![CellType::Tent, CellType::Unknown, CellType::Tree],
+                vec![CellType::Unknown, CellType::Tent, CellType::Unknown],
+                vec![CellType::Tree, CellType::Empty, CellType::Unknown],
+            ],
+            vec![Some(1), Some(4), Some(1)],
+            vec![Some(1), Some(1), Some(1)],
+        );
+        assert_eq!(
+            board.validate(),
+            [
+                Violation::TouchingTents((0, 0), (1, 1)),
+                Violation::TentWithoutTree(0, 0),
+                Violation::TentWithoutTree(1, 1),
+                Violation::ClueTooLarge {
+                    axis: Axis::Column,
+                    line: 1,
+                    clue: 4,
+                    cells: 3
+                },
+                Violation::ClueSumsDisagree {
+                    axis: Axis::Row,
+                    sum: 3,
+                    other_axis: Axis::Column,
+                    other_sum: 6
+                },
+                Violation::ClueSumNotTreeCount {
+                    axis: Axis::Row,
+                    sum: 3,
+                    trees: 2
+                },
+                Violation::ClueSumNotTreeCount {
+                    axis: Axis::Column,
+                    sum: 6,
+                    trees: 2
+                },
+            ]
+        );
+
+        let jagged = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown],
+                vec![CellType::Unknown],
+            ],
+            vec![None, None],
+            vec![None, None],
+        );
+        assert_eq!(
+            jagged.validate(),
+            [Violation::JaggedRow {
+                row: 1,
+                len: 1,
+                expected: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn rectangular_boards_have_the_right_shape() {
+        let mut board = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown, CellType::Tree],
+            ],
+            vec![Some(1), None, Some(1)],
+            vec![Some(1), Some(1)],
+        );
+        assert_eq!((board.row_count, board.col_count), (2, 3));
+        assert_eq!(board.validate(), []);
+        let original = board.clone();
+        board.set_celltype(1, 1, CellType::Tent);
+        assert_ne!(board, original);
+        assert_eq!(board.col_counts(1).tents, 1);
+
+        let display = board.to_string();
+        assert_eq!(display.lines().count(), 4);
+        assert!(display.lines().all(|line| line.len() == 13));
+
+        board.rules.wrap_horizontal = true;
+        let neighbours: Vec<(usize, usize)> = board.tent_neighbours(0, 2).collect();
+        assert_eq!(neighbours, [(0, 1), (0, 0), (1, 2), (1, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn narrow_wrapped_boards_list_each_neighbour_once() {
+        let mut board = Board::new(
+            vec![
+                vec![
+                    CellType::Tree,
+                    CellType::Unknown,
+                    CellType::Unknown,
+                    CellType::Unknown,
+                ],
+                vec![
+                    CellType::Unknown,
+                    CellType::Unknown,
+                    CellType::Unknown,
+                    CellType::Unknown,
+                ],
+            ],
+            vec![None, None, None, None],
+            vec![None, None],
+        );
+        board.rules.wrap_horizontal = true;
+        board.rules.wrap_vertical = true;
+        let mut neighbours: Vec<(usize, usize)> = board.tent_neighbours(0, 0).collect();
+        neighbours.sort();
+        assert_eq!(neighbours, [(0, 1), (0, 3), (1, 0), (1, 1), (1, 3)]);
+
+        // Up and down from the tree both reach (1, 0), so only one of them is offered
+        let directions = board.get_possible_tent_directions(0, 0);
+        assert_eq!(directions.len(), 3);
+        let mut cells: Vec<(usize, usize)> = directions
+            .iter()
+            .map(|direction| board.get_possible_tent_position(0, 0, direction).unwrap())
+            .collect();
+        cells.sort();
+        assert_eq!(cells, [(0, 1), (0, 3), (1, 0)]);
+    }
+
+    #[test]
+    fn hash_depends_only_on_cells() {
+        let mut first = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown, CellType::Tree],
+            ],
+            vec![None, None, None],
+            vec![None, None],
+        );
+        let mut second = first.clone();
+        let initial = first.zobrist_hash();
+
+        first.set_celltype(0, 1, CellType::Tent);
+        first.set_celltype(1, 1, CellType::Empty);
+        second.set_celltype(1, 1, CellType::Empty);
+        second.set_celltype(0, 2, CellType::Tent);
+        second.set_celltype(0, 2, CellType::Unknown);
+        second.set_celltype(0, 1, CellType::Tent);
+        assert_eq!(first.zobrist_hash(), second.zobrist_hash());
+        assert_ne!(first.zobrist_hash(), initial);
+
+        first.set_celltype(0, 1, CellType::Unknown);
+        first.set_celltype(1, 1, CellType::Unknown);
+        assert_eq!(first.zobrist_hash(), initial);
+    }
+
+    #[test]
+    fn rollback_undoes_changes_since_checkpoint() {
+        let mut board = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown, CellType::Tree],
+                vec![CellType::Unknown, CellType::Unknown, CellType::Unknown],
+            ],
+            vec![Some(1), Some(0), Some(1)],
+            vec![Some(1), None, Some(1)],
+        );
+        board.enable_journal();
+        let original = board.clone();
+        let start = board.checkpoint();
+
+        assert!(board.place_tent(1, 0) > 0);
+        let after_first = board.clone();
+        let middle = board.checkpoint();
+        board.place_tent(0, 2);
+        assert_ne!(board, after_first);
+
+        board.rollback(middle);
+        assert_eq!(board, after_first);
+        assert_eq!(board.row_counts(0), after_first.row_counts(0));
+        board.rollback(start);
+        assert_eq!(board, original);
+        assert_eq!(board.col_counts(0), original.col_counts(0));
+        assert_eq!(board.zobrist_hash(), original.zobrist_hash());
+    }
+}
@@ -0,0 +1,435 @@
+use std::time::Duration;
+
+use crate::{
+    backtracker::{RestartPolicy, SearchLimits},
+    parser,
+    pipe::PipeOptions,
+    server::ServerOptions,
+    AppError,
+};
+
+/// The puzzle solved when no file is given
+const DEFAULT_PUZZLE: &str = "examples/example1.txt";
+
+pub const USAGE: &str = "Usage: tents_and_trees [solve] [FILE] [OPTIONS]
+       tents_and_trees check <FILE> [ATTEMPT]
+       tents_and_trees play <FILE> [SAVE]
+       tents_and_trees serve [--port <PORT>] [--timeout <SECONDS>] [--max-request-bytes <BYTES>]
+                             [--max-connections <COUNT>]
+       tents_and_trees pipe [--timeout <SECONDS>] [--max-line-bytes <BYTES>]
+
+Commands:
+    solve                  Solve the puzzle (the default), examples/example1.txt unless a
+                           FILE is given
+    check                  List every problem that stops the puzzle from being valid, or with
+                           an attempt, every rule the attempt breaks
+    play                   Solve the puzzle yourself in the terminal, saving the game to SAVE
+                           (default <FILE>.save) and resuming it from there if it exists
+    serve                  Answer JSON requests to /solve, /validate, /hint, /generate and
+                           /render over HTTP on localhost (port 8080 unless --port is given).
+                           --timeout limits the searches for each request (default 10 seconds)
+                           and --max-request-bytes the size of a request (default 65536).
+                           Past --max-connections at once (default 4), requests get a 503
+    pipe                   Answer each line of stdin with a line of JSON on stdout. A line is
+                           a puzzle with its rows separated by ';', or a JSON object like the
+                           body of a request to serve, with an optional \"action\" naming the
+                           endpoint and \"id\" to copy to the result. --timeout limits each
+                           line (default 10 seconds) and --max-line-bytes its length (default
+                           65536)
+
+Options:
+    --timeout <SECONDS>    Give up if the puzzle is not solved within this many seconds
+    --max-nodes <COUNT>    Give up after expanding this many search nodes
+    --stats                Print statistics about the search
+    --animate              Redraw the board at every step of the search
+    --delay <MS>           Milliseconds to pause between animation frames (default 100)
+    --in-place             Search by changing one board and undoing moves, instead of cloning it
+    --diagonal <RULE>      Whether tents may touch diagonally, allowed or forbidden, whatever
+                           the puzzle file says
+    --wrap <EDGES>         Make the edges wrap around: none, horizontal, vertical or both
+    --seed <N>             Try trees and tent positions in a random order chosen by the seed
+    --restarts <COUNT>     Restart the random search up to this many times with growing node
+                           limits (seed 0 unless --seed is given)";
+
+/// The action requested on the command line
+#[derive(Debug)]
+pub enum Command {
+    Solve(SolveOptions),
+    /// Validate a puzzle, or verify an attempt at solving it if one is given
+    Check {
+        file_path: String,
+        attempt: Option<String>,
+    },
+    /// Play the puzzle interactively in the terminal, saving the game to a file
+    Play {
+        file_path: String,
+        save_path: Option<String>,
+    },
+    /// Run the HTTP service
+    Serve(ServerOptions),
+    /// Answer puzzles from stdin, one per line
+    Pipe(PipeOptions),
+}
+
+#[derive(Debug)]
+pub struct SolveOptions {
+    pub file_path: String,
+    pub limits: SearchLimits,
+    pub stats: bool,
+    /// The delay between frames, if the search should be animated
+    pub animate: Option<Duration>,
+    /// Whether to use the apply/undo search instead of cloning every successor
+    pub in_place: bool,
+    /// The seed for a randomised search order
+    pub seed: Option<u64>,
+    pub restarts: Option<RestartPolicy>,
+    /// Whether tents may touch diagonally, overriding the puzzle's rules
+    pub diagonal: Option<bool>,
+    /// Which edges wrap around as (horizontal, vertical), overriding the puzzle's rules
+    pub wrap: Option<(bool, bool)>,
+}
+
+/// Parse the command line arguments, not including the program name
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, AppError> {
+    let mut args = args.into_iter().peekable();
+    match args.peek().map(String::as_str) {
+        Some("solve") => {
+            args.next();
+        }
+        Some("check") => {
+            args.next();
+            let file_path = args
+                .next()
+                .ok_or_else(|| usage_error("No puzzle file given".to_string()))?;
+            let attempt = args.next();
+            if let Some(arg) = args.next() {
+                return Err(usage_error(format!("Unexpected argument {}", arg)));
+            }
+            return Ok(Command::Check { file_path, attempt });
+        }
+        Some("play") => {
+            args.next();
+            let file_path = args
+                .next()
+                .ok_or_else(|| usage_error("No puzzle file given".to_string()))?;
+            let save_path = args.next();
+            if let Some(arg) = args.next() {
+                return Err(usage_error(format!("Unexpected argument {}", arg)));
+            }
+            return Ok(Command::Play {
+                file_path,
+                save_path,
+            });
+        }
+        Some("serve") => {
+            args.next();
+            return parse_serve_args(args);
+        }
+        Some("pipe") => {
+            args.next();
+            return parse_pipe_args(args);
+        }
+        _ => {}
+    }
+
+    let mut file_path = None;
+    let mut limits = SearchLimits::default();
+    let mut stats = false;
+    let mut animate = false;
+    let mut in_place = false;
+    let mut seed = None;
+    let mut restarts = None;
+    let mut diagonal = None;
+    let mut wrap = None;
+    let mut delay = Duration::from_millis(100);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--timeout" => {
+                let seconds: f64 = parse_value(&arg, args.next())?;
+                let timeout = Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| usage_error(format!("Invalid value for {}", arg)))?;
+                limits = limits.with_timeout(timeout);
+            }
+            "--max-nodes" => limits = limits.with_max_nodes(parse_value(&arg, args.next())?),
+            "--stats" => stats = true,
+            "--animate" => animate = true,
+            "--in-place" => in_place = true,
+            "--diagonal" => {
+                diagonal = Some(
+                    args.next()
+                        .and_then(|value| parser::parse_diagonal(&value))
+                        .ok_or_else(|| {
+                            usage_error(format!("Invalid or missing value for {}", arg))
+                        })?,
+                )
+            }
+            "--wrap" => {
+                wrap = Some(
+                    args.next()
+                        .and_then(|value| parser::parse_wrap(&value))
+                        .ok_or_else(|| {
+                            usage_error(format!("Invalid or missing value for {}", arg))
+                        })?,
+                )
+            }
+            "--seed" => seed = Some(parse_value(&arg, args.next())?),
+            "--restarts" => {
+                restarts = Some(RestartPolicy {
+                    max_restarts: parse_value(&arg, args.next())?,
+                    ..RestartPolicy::default()
+                })
+            }
+            "--delay" => delay = Duration::from_millis(parse_value(&arg, args.next())?),
+            _ if arg.starts_with("--") => {
+                return Err(usage_error(format!("Unknown option {}", arg)))
+            }
+            _ if file_path.is_none() => file_path = Some(arg),
+            _ => return Err(usage_error(format!("Unexpected argument {}", arg))),
+        }
+    }
+
+    let file_path = file_path.unwrap_or_else(|| DEFAULT_PUZZLE.to_string());
+    if (seed.is_some() || restarts.is_some()) && (animate || in_place) {
+        return Err(usage_error(
+            "A randomised search cannot be combined with --animate or --in-place".to_string(),
+        ));
+    }
+    if restarts.is_some() && seed.is_none() {
+        seed = Some(0);
+    }
+    Ok(Command::Solve(SolveOptions {
+        file_path,
+        limits,
+        stats,
+        animate: animate.then_some(delay),
+        in_place,
+        seed,
+        restarts,
+        diagonal,
+        wrap,
+    }))
+}
+
+fn parse_serve_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, AppError> {
+    let mut options = ServerOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => options.port = parse_value(&arg, args.next())?,
+            "--timeout" => {
+                let seconds: f64 = parse_value(&arg, args.next())?;
+                options.solve_timeout = Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| usage_error(format!("Invalid value for {}", arg)))?;
+            }
+            "--max-request-bytes" => options.max_request_bytes = parse_value(&arg, args.next())?,
+            "--max-connections" => options.max_connections = parse_value(&arg, args.next())?,
+            _ => return Err(usage_error(format!("Unexpected argument {}", arg))),
+        }
+    }
+    Ok(Command::Serve(options))
+}
+
+fn parse_pipe_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, AppError> {
+    let mut options = PipeOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--timeout" => {
+                let seconds: f64 = parse_value(&arg, args.next())?;
+                options.solve_timeout = Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| usage_error(format!("Invalid value for {}", arg)))?;
+            }
+            "--max-line-bytes" => options.max_line_bytes = parse_value(&arg, args.next())?,
+            _ => return Err(usage_error(format!("Unexpected argument {}", arg))),
+        }
+    }
+    Ok(Command::Pipe(options))
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, AppError> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| usage_error(format!("Invalid or missing value for {}", option)))
+}
+
+fn usage_error(message: String) -> AppError {
+    AppError::UsageError(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn solve_options(arguments: &[&str]) -> SolveOptions {
+        match parse_args(args(arguments)).unwrap() {
+            Command::Solve(options) => options,
+            command => panic!("Expected a solve command, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn solve_with_limits() {
+        let options = solve_options(&[
+            "solve",
+            "puzzle.txt",
+            "--timeout",
+            "1.5",
+            "--max-nodes",
+            "1000",
+            "--stats",
+            "--in-place",
+            "--diagonal",
+            "allowed",
+            "--wrap",
+            "horizontal",
+        ]);
+        assert_eq!(options.file_path, "puzzle.txt");
+        assert!(options.limits.deadline.is_some());
+        assert_eq!(options.limits.max_nodes, Some(1000));
+        assert!(options.stats);
+        assert!(options.in_place);
+        assert_eq!(options.diagonal, Some(true));
+        assert_eq!(options.wrap, Some((true, false)));
+    }
+
+    #[test]
+    fn solve_is_the_default_command() {
+        let options = solve_options(&["puzzle.txt"]);
+        assert_eq!(options.file_path, "puzzle.txt");
+        assert!(options.limits.deadline.is_none());
+        assert!(!options.stats);
+        assert!(options.animate.is_none());
+        assert!(options.diagonal.is_none());
+        assert!(options.wrap.is_none());
+
+        let options = solve_options(&[]);
+        assert_eq!(options.file_path, DEFAULT_PUZZLE);
+        let options = solve_options(&["solve", "--stats"]);
+        assert_eq!(options.file_path, DEFAULT_PUZZLE);
+        assert!(options.stats);
+    }
+
+    #[test]
+    fn animate_with_delay() {
+        let options = solve_options(&["solve", "puzzle.txt", "--delay", "20", "--animate"]);
+        assert_eq!(options.animate, Some(Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn randomised_search() {
+        let options = solve_options(&["puzzle.txt", "--restarts", "5"]);
+        assert_eq!(options.seed, Some(0));
+        assert_eq!(options.restarts.map(|policy| policy.max_restarts), Some(5));
+
+        let options = solve_options(&["puzzle.txt", "--seed", "12"]);
+        assert_eq!(options.seed, Some(12));
+        assert!(options.restarts.is_none());
+
+        assert!(matches!(
+            parse_args(args(&["puzzle.txt", "--seed", "12", "--in-place"])),
+            Err(AppError::UsageError(_))
+        ));
+    }
+
+    #[test]
+    fn check_command() {
+        assert!(matches!(
+            parse_args(args(&["check", "puzzle.txt"])),
+            Ok(Command::Check { file_path, attempt: None }) if file_path == "puzzle.txt"
+        ));
+        assert!(matches!(
+            parse_args(args(&["check", "puzzle.txt", "attempt.txt"])),
+            Ok(Command::Check { attempt: Some(attempt), .. }) if attempt == "attempt.txt"
+        ));
+        assert!(matches!(
+            parse_args(args(&["check", "puzzle.txt", "attempt.txt", "--stats"])),
+            Err(AppError::UsageError(_))
+        ));
+    }
+
+    #[test]
+    fn play_command() {
+        assert!(matches!(
+            parse_args(args(&["play", "puzzle.txt"])),
+            Ok(Command::Play { file_path, save_path: None }) if file_path == "puzzle.txt"
+        ));
+        assert!(matches!(
+            parse_args(args(&["play", "puzzle.txt", "game.save"])),
+            Ok(Command::Play { save_path: Some(save_path), .. }) if save_path == "game.save"
+        ));
+        assert!(matches!(
+            parse_args(args(&["play"])),
+            Err(AppError::UsageError(_))
+        ));
+    }
+
+    #[test]
+    fn serve_command() {
+        let Ok(Command::Serve(options)) = parse_args(args(&["serve"])) else {
+            panic!("Expected a serve command");
+        };
+        assert_eq!(options, ServerOptions::default());
+        let Ok(Command::Serve(options)) = parse_args(args(&[
+            "serve",
+            "--port",
+            "9000",
+            "--timeout",
+            "2.5",
+            "--max-request-bytes",
+            "1024",
+            "--max-connections",
+            "16",
+        ])) else {
+            panic!("Expected a serve command");
+        };
+        assert_eq!(options.port, 9000);
+        assert_eq!(options.solve_timeout, Duration::from_millis(2500));
+        assert_eq!(options.max_request_bytes, 1024);
+        assert_eq!(options.max_connections, 16);
+        assert!(matches!(
+            parse_args(args(&["serve", "puzzle.txt"])),
+            Err(AppError::UsageError(_))
+        ));
+    }
+
+    #[test]
+    fn pipe_command() {
+        let Ok(Command::Pipe(options)) = parse_args(args(&[
+            "pipe",
+            "--timeout",
+            "0.5",
+            "--max-line-bytes",
+            "100",
+        ])) else {
+            panic!("Expected a pipe command");
+        };
+        assert_eq!(options.solve_timeout, Duration::from_millis(500));
+        assert_eq!(options.max_line_bytes, 100);
+        assert!(matches!(
+            parse_args(args(&["pipe", "--port", "1"])),
+            Err(AppError::UsageError(_))
+        ));
+    }
+
+    #[test]
+    fn bad_arguments() {
+        assert!(matches!(
+            parse_args(args(&["puzzle.txt", "--max-nodes", "many"])),
+            Err(AppError::UsageError(_))
+        ));
+        assert!(matches!(
+            parse_args(args(&["puzzle.txt", "--verbose"])),
+            Err(AppError::UsageError(_))
+        ));
+        assert!(matches!(
+            parse_args(args(&["puzzle.txt", "--wrap", "sideways"])),
+            Err(AppError::UsageError(_))
+        ));
+        assert!(matches!(
+            parse_args(args(&["puzzle.txt", "--diagonal"])),
+            Err(AppError::UsageError(_))
+        ));
+    }
+}
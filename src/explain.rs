@@ -0,0 +1,238 @@
+use std::fmt::Display;
+
+use crate::{
+    backtracker::{self, Outcome, SearchLimits},
+    board::{Axis, Board, CellType},
+    solver::TentsAndTreesConfig,
+};
+
+/// The node limit for each check when explaining, if the limits given do not set one
+pub const DEFAULT_CHECK_NODES: usize = 100_000;
+
+/// A part of a puzzle that can be blamed for it having no solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constraint {
+    Clue {
+        axis: Axis,
+        line: usize,
+        clue: usize,
+    },
+    Tree(usize, usize),
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::Clue { axis, line, clue } => {
+                write!(f, "the clue {} for {} {}", clue, axis, line)
+            }
+            Constraint::Tree(row, col) => write!(f, "the tree at ({}, {})", row, col),
+        }
+    }
+}
+
+/// A set of clues or trees that cannot all be satisfied together
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub conflict: Vec<Constraint>,
+    /// Whether every check finished within its limits. If not, some of the constraints could
+    /// not be ruled out, and the conflict may not be as small as it could be.
+    pub minimal: bool,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "These cannot all be satisfied together:")?;
+        for constraint in &self.conflict {
+            writeln!(f, "  {}", constraint)?;
+        }
+        if !self.minimal {
+            writeln!(f, "Some checks gave up, so not all of these may be needed")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find a small set of clues or trees that make the puzzle unsolvable, by removing them one at
+/// a time and keeping only those whose removal lets the puzzle be solved. Each check is a full
+/// search, limited by `limits`, whose node limit applies to each check separately. The board
+/// should not have had any cells filled in by `set_mandatory_empty`, as they depend on the clues.
+///
+/// The trees are only blamed when the puzzle has no solution even without its clues, so the
+/// conflict is either made of clues, which need every tree, or of trees alone. Returns `None`
+/// if the puzzle has a solution, or could not be shown to have none within the limits.
+pub fn explain(board: &Board, limits: &SearchLimits) -> Option<Explanation> {
+    let limits = SearchLimits {
+        max_nodes: limits.max_nodes.or(Some(DEFAULT_CHECK_NODES)),
+        ..limits.clone()
+    };
+    if check(board, &limits) != Some(false) {
+        return None;
+    }
+
+    let clues = clue_constraints(board);
+    // The constraints to try removing, and those removed from the start
+    let (constraints, mut removed) = if check(&without(board, &clues), &limits) == Some(false) {
+        let trees = board
+            .get_tree_positions()
+            .into_iter()
+            .map(|(row, col, _)| Constraint::Tree(row, col))
+            .collect();
+        (trees, clues)
+    } else {
+        (clues, Vec::new())
+    };
+
+    // Removing a clue or a tree only makes the puzzle easier, unless a given tent needs the
+    // tree, so a constraint is kept if the puzzle becomes solvable without it
+    let mut conflict = Vec::new();
+    let mut minimal = true;
+    for constraint in constraints {
+        removed.push(constraint);
+        match check(&without(board, &removed), &limits) {
+            Some(false) => {}
+            Some(true) => {
+                removed.pop();
+                conflict.push(constraint);
+            }
+            None => {
+                removed.pop();
+                conflict.push(constraint);
+                minimal = false;
+            }
+        }
+    }
+    Some(Explanation { conflict, minimal })
+}
+
+/// Get every clue on the board as a constraint
+fn clue_constraints(board: &Board) -> Vec<Constraint> {
+    board
+        .axes()
+        .flat_map(|axis| {
+            (0..board.clues(axis).len()).filter_map(move |line| {
+                let clue = board.clue(axis, line)?;
+                Some(Constraint::Clue { axis, line, clue })
+            })
+        })
+        .collect()
+}
+
+/// Get a copy of the board with the constraints taken away: clues are cleared, and trees are
+/// replaced with empty cells
+fn without(board: &Board, constraints: &[Constraint]) -> Board {
+    let mut board = board.clone();
+    for constraint in constraints {
+        match constraint {
+            Constraint::Clue { axis, line, .. } => board.set_clue(*axis, *line, None),
+            Constraint::Tree(row, col) => board.set_celltype(*row, *col, CellType::Empty),
+        }
+    }
+    board
+}
+
+/// Check whether the board has a solution, or `None` if the search gave up
+fn check(board: &Board, limits: &SearchLimits) -> Option<bool> {
+    let mut board = board.clone();
+    board.set_mandatory_empty();
+    match backtracker::solve(TentsAndTreesConfig::new(&board), limits).outcome {
+        Outcome::Solved(_) => Some(true),
+        Outcome::Unsolvable => Some(false),
+        Outcome::GaveUp(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn conflicting_clues_are_minimal() {
+        let board = parser::get_board_from_file("examples/example_unsolvable.txt").unwrap();
+        let limits = SearchLimits::default();
+        let explanation = explain(&board, &limits).unwrap();
+        assert!(explanation.minimal);
+        assert!(!explanation.conflict.is_empty());
+        assert!(explanation
+            .conflict
+            .iter()
+            .all(|constraint| matches!(constraint, Constraint::Clue { .. })));
+
+        // Only the conflicting clues are needed for the puzzle to have no solution, and
+        // dropping any one of them makes it solvable
+        let other_clues: Vec<Constraint> = clue_constraints(&board)
+            .into_iter()
+            .filter(|clue| !explanation.conflict.contains(clue))
+            .collect();
+        assert_eq!(check(&without(&board, &other_clues), &limits), Some(false));
+        for constraint in &explanation.conflict {
+            let mut removed = other_clues.clone();
+            removed.push(*constraint);
+            assert_eq!(check(&without(&board, &removed), &limits), Some(true));
+        }
+    }
+
+    #[test]
+    fn trees_are_blamed_without_clues() {
+        let board = Board::new(
+            vec![
+                vec![
+                    CellType::Tree,
+                    CellType::Unknown,
+                    CellType::Tree,
+                    CellType::Blocked,
+                ],
+                vec![
+                    CellType::Blocked,
+                    CellType::Blocked,
+                    CellType::Blocked,
+                    CellType::Unknown,
+                ],
+                vec![CellType::Unknown; 4],
+                vec![
+                    CellType::Unknown,
+                    CellType::Unknown,
+                    CellType::Unknown,
+                    CellType::Tree,
+                ],
+            ],
+            vec![None, None, None, Some(1)],
+            vec![None, None, None, Some(1)],
+        );
+        let explanation = explain(&board, &SearchLimits::default()).unwrap();
+        assert_eq!(
+            explanation.conflict,
+            [Constraint::Tree(0, 0), Constraint::Tree(0, 2)]
+        );
+    }
+
+    #[test]
+    fn transcription_typo_is_explained() {
+        // The last row of example_fail is missing one of example1's trees
+        let board = parser::get_board_from_file("examples/example_fail.txt").unwrap();
+        let explanation = explain(&board, &SearchLimits::default()).unwrap();
+        assert!(explanation.minimal);
+        assert_eq!(
+            explanation.conflict,
+            [
+                Constraint::Clue {
+                    axis: Axis::Column,
+                    line: 3,
+                    clue: 3
+                },
+                Constraint::Clue {
+                    axis: Axis::Column,
+                    line: 5,
+                    clue: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn solvable_puzzles_have_no_explanation() {
+        let board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        assert_eq!(explain(&board, &SearchLimits::default()), None);
+    }
+}
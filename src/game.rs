@@ -0,0 +1,717 @@
+use std::{
+    cell::OnceCell,
+    fmt::Display,
+    fs,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    backtracker::{self, Outcome, SearchLimits},
+    board::{Axis, Board, CellType},
+    parser::{self, ParseErrorType},
+    solver::TentsAndTreesConfig,
+    verify::{self, Mistake},
+    AppError,
+};
+
+/// How long to spend looking for the solution used for hints
+const SOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The headings of the sections of a save file
+const GAME_SECTION: &str = "[game]";
+const PUZZLE_SECTION: &str = "[puzzle]";
+const MOVES_SECTION: &str = "[moves]";
+
+/// A change to a single cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edit {
+    pub row: usize,
+    pub col: usize,
+    pub old: CellType,
+    pub new: CellType,
+}
+
+/// What made a move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveKind {
+    /// The player marked a cell
+    Mark,
+    /// A hint corrected a cell or placed a tent
+    Hint,
+    /// The rest of the solution was revealed
+    Reveal,
+}
+
+impl MoveKind {
+    fn name(&self) -> &'static str {
+        match self {
+            MoveKind::Mark => "mark",
+            MoveKind::Hint => "hint",
+            MoveKind::Reveal => "reveal",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [MoveKind::Mark, MoveKind::Hint, MoveKind::Reveal]
+            .into_iter()
+            .find(|kind| kind.name() == name)
+    }
+}
+
+/// A single action, which is undone and redone as a whole
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub kind: MoveKind,
+    pub edits: Vec<Edit>,
+}
+
+/// The help given by a hint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hint {
+    /// A cell that did not match the solution was corrected
+    Corrected { row: usize, col: usize },
+    /// A tent from the solution was placed
+    Tent { row: usize, col: usize },
+}
+
+impl Display for Hint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hint::Corrected { row, col } => write!(f, "The cell at ({}, {}) was wrong", row, col),
+            Hint::Tent { row, col } => write!(f, "There is a tent at ({}, {})", row, col),
+        }
+    }
+}
+
+/// The help the game gives the player, each of which can be turned on and off
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Assists {
+    /// Mark the cells around a new tent as grass
+    pub grass_around_tents: bool,
+    /// Mark the rest of a line as grass once it has as many tents as its clue
+    pub fill_finished_lines: bool,
+    /// Show the trees that are already paired with a tent as done
+    pub grey_paired_trees: bool,
+    /// Report rules the board breaks as soon as they are broken
+    pub warn_on_mistakes: bool,
+}
+
+impl Assists {
+    /// The names of the assists in save files, in the order of the fields
+    pub const NAMES: [&'static str; 4] = ["grass", "lines", "trees", "warnings"];
+
+    /// Get the setting of each assist, in the order of the fields
+    pub fn flags(&self) -> [bool; 4] {
+        [
+            self.grass_around_tents,
+            self.fill_finished_lines,
+            self.grey_paired_trees,
+            self.warn_on_mistakes,
+        ]
+    }
+
+    pub fn flags_mut(&mut self) -> [&mut bool; 4] {
+        [
+            &mut self.grass_around_tents,
+            &mut self.fill_finished_lines,
+            &mut self.grey_paired_trees,
+            &mut self.warn_on_mistakes,
+        ]
+    }
+}
+
+/// A puzzle being played: the player's board, the moves made so far, the time taken and the hints
+/// used. Front-ends turn input into calls on a `Game` and draw its board.
+pub struct Game {
+    pub assists: Assists,
+    puzzle: Board,
+    board: Board,
+    // The limits on the search for the solution, or `None` to search for up to `SOLVE_TIMEOUT`
+    // from when the solution is first needed
+    limits: Option<SearchLimits>,
+    // The solution used for hints, looked for the first time it is needed
+    solution: OnceCell<Option<Board>>,
+    // The moves made, oldest first
+    history: Vec<Move>,
+    // The moves undone, most recently undone last
+    undone: Vec<Move>,
+    hints_used: usize,
+    // The time played before `started`, such as in an earlier session
+    played: Duration,
+    started: Instant,
+    // The time taken to solve the puzzle. The clock is stopped while the puzzle stays solved.
+    solved_in: Option<Duration>,
+}
+
+impl Game {
+    pub fn new(puzzle: Board) -> Self {
+        Game {
+            assists: Assists::default(),
+            board: puzzle.clone(),
+            limits: None,
+            solution: OnceCell::new(),
+            puzzle,
+            history: Vec::new(),
+            undone: Vec::new(),
+            hints_used: 0,
+            played: Duration::ZERO,
+            started: Instant::now(),
+            solved_in: None,
+        }
+    }
+
+    /// Start a game, with limits on the search for the solution used for hints
+    pub fn with_limits(puzzle: Board, limits: &SearchLimits) -> Self {
+        Game {
+            limits: Some(limits.clone()),
+            ..Self::new(puzzle)
+        }
+    }
+
+    pub fn puzzle(&self) -> &Board {
+        &self.puzzle
+    }
+
+    /// Get the board with the player's marks
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Get the moves made, oldest first, not including any that have been undone
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Check whether a solution was found, without which hints are not available. This searches
+    /// for it if it has not been needed yet.
+    pub fn has_solution(&self) -> bool {
+        self.solution().is_some()
+    }
+
+    /// Get the solution used for hints, searching for it the first time it is needed
+    fn solution(&self) -> Option<&Board> {
+        self.solution
+            .get_or_init(|| {
+                let limits = self
+                    .limits
+                    .clone()
+                    .unwrap_or_else(|| SearchLimits::default().with_timeout(SOLVE_TIMEOUT));
+                find_solution(&self.puzzle, &limits)
+            })
+            .as_ref()
+    }
+
+    /// Check whether a cell was given in the puzzle, so cannot be changed
+    pub fn is_given(&self, row: usize, col: usize) -> bool {
+        self.puzzle.get_celltype(row, col) != CellType::Unknown
+    }
+
+    pub fn is_solved(&self) -> bool {
+        // Counting the tents is cheap next to verifying the board, and rules out most boards
+        for axis in [Axis::Row, Axis::Column] {
+            let lines = 0..self.puzzle.clues(axis).len();
+            let clued: Option<usize> = lines.clone().map(|line| self.puzzle.clue(axis, line)).sum();
+            let tents: usize = lines
+                .map(|line| self.board.line_counts(axis, line).tents)
+                .sum();
+            if clued.is_some_and(|clued| clued != tents) {
+                return false;
+            }
+        }
+        verify::verify(&self.puzzle, &self.board).is_empty()
+    }
+
+    pub fn hints_used(&self) -> usize {
+        self.hints_used
+    }
+
+    /// Get the time spent playing, which stops once the puzzle is solved
+    pub fn elapsed(&self) -> Duration {
+        self.solved_in
+            .unwrap_or_else(|| self.played + self.started.elapsed())
+    }
+
+    /// Mark a cell as unknown, grass or a tent. Returns whether the board changed, which it does
+    /// not for cells given in the puzzle.
+    pub fn set_cell(&mut self, row: usize, col: usize, cell: CellType) -> bool {
+        let old = self.board.get_celltype(row, col);
+        if self.is_given(row, col)
+            || old == cell
+            || !matches!(cell, CellType::Unknown | CellType::Empty | CellType::Tent)
+        {
+            return false;
+        }
+        self.make_move(
+            MoveKind::Mark,
+            vec![Edit {
+                row,
+                col,
+                old,
+                new: cell,
+            }],
+        );
+        true
+    }
+
+    /// Make a move, adding the grass marked by the assists for any tents it places
+    fn make_move(&mut self, kind: MoveKind, mut edits: Vec<Edit>) {
+        for edit in &edits {
+            self.board.set_celltype(edit.row, edit.col, edit.new);
+        }
+        if kind != MoveKind::Reveal {
+            let tents: Vec<(usize, usize)> = edits
+                .iter()
+                .filter(|edit| edit.new == CellType::Tent)
+                .map(|edit| (edit.row, edit.col))
+                .collect();
+            for (row, col) in tents {
+                self.assist_tent(row, col, &mut edits);
+            }
+        }
+        self.history.push(Move { kind, edits });
+        self.undone.clear();
+        self.update_clock();
+    }
+
+    /// Mark the cells the assists rule out once a tent is placed as grass, adding the edits to the
+    /// move placing the tent so they are undone with it
+    fn assist_tent(&mut self, row: usize, col: usize, edits: &mut Vec<Edit>) {
+        let mut grass = Vec::new();
+        if self.assists.grass_around_tents {
+            grass.extend(self.board.tent_neighbours(row, col));
+        }
+        if self.assists.fill_finished_lines {
+            for axis in self.board.axes() {
+                let Some(line) = self.board.line(axis, row, col) else {
+                    continue;
+                };
+                if self.board.clue(axis, line) == Some(self.board.line_counts(axis, line).tents) {
+                    grass.extend(self.board.line_cells(axis, line));
+                }
+            }
+        }
+        for (r, c) in grass {
+            if self.board.get_celltype(r, c) == CellType::Unknown {
+                self.board.set_celltype(r, c, CellType::Empty);
+                edits.push(Edit {
+                    row: r,
+                    col: c,
+                    old: CellType::Unknown,
+                    new: CellType::Empty,
+                });
+            }
+        }
+    }
+
+    /// Get the trees that already have a tent of their own, to be shown as done. This is empty
+    /// unless the assist is on.
+    pub fn paired_trees(&self) -> Vec<(usize, usize)> {
+        if !self.assists.grey_paired_trees {
+            return Vec::new();
+        }
+        let tents = self.board.get_tent_positions();
+        self.board
+            .pair_tents(&tents)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Get the rules the board already breaks, whatever goes in the unmarked cells. This is empty
+    /// unless the assist is on.
+    pub fn warnings(&self) -> Vec<Mistake> {
+        if !self.assists.warn_on_mistakes {
+            return Vec::new();
+        }
+        verify::broken_rules(&self.board)
+    }
+
+    /// Undo the last move, returning `false` if there is none
+    pub fn undo(&mut self) -> bool {
+        let Some(last) = self.history.pop() else {
+            return false;
+        };
+        for edit in last.edits.iter().rev() {
+            self.board.set_celltype(edit.row, edit.col, edit.old);
+        }
+        self.undone.push(last);
+        self.update_clock();
+        true
+    }
+
+    /// Redo the last move undone, returning `false` if there is none
+    pub fn redo(&mut self) -> bool {
+        let Some(next) = self.undone.pop() else {
+            return false;
+        };
+        for edit in &next.edits {
+            self.board.set_celltype(edit.row, edit.col, edit.new);
+        }
+        self.history.push(next);
+        self.update_clock();
+        true
+    }
+
+    /// Stop the clock when the puzzle becomes solved, and start it again if a move is undone
+    fn update_clock(&mut self) {
+        match (self.is_solved(), self.solved_in) {
+            (true, None) => self.solved_in = Some(self.elapsed()),
+            (false, Some(time)) => {
+                self.played = time;
+                self.started = Instant::now();
+                self.solved_in = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Get what a cell should be in the solution, counting unmarked cells as grass
+    fn solution_cell(solution: &Board, row: usize, col: usize) -> CellType {
+        match solution.get_celltype(row, col) {
+            CellType::Unknown => CellType::Empty,
+            cell => cell,
+        }
+    }
+
+    /// Get the cells the player can change, row by row
+    fn open_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.puzzle.row_count)
+            .flat_map(|row| (0..self.puzzle.col_count).map(move |col| (row, col)))
+            .filter(|(row, col)| !self.is_given(*row, *col))
+    }
+
+    /// Correct the first cell that differs from the solution, or if there are none, place the
+    /// first missing tent. Returns `None` if there is no solution or nothing left to do.
+    pub fn hint(&mut self) -> Option<Hint> {
+        let solution = self.solution()?;
+        let wrong = self.open_cells().find(|(row, col)| {
+            let cell = self.board.get_celltype(*row, *col);
+            cell != CellType::Unknown && cell != Self::solution_cell(solution, *row, *col)
+        });
+        let hint = match wrong {
+            Some((row, col)) => Hint::Corrected { row, col },
+            None => {
+                let (row, col) = self.open_cells().find(|(row, col)| {
+                    self.board.get_celltype(*row, *col) == CellType::Unknown
+                        && solution.get_celltype(*row, *col) == CellType::Tent
+                })?;
+                Hint::Tent { row, col }
+            }
+        };
+        let (Hint::Corrected { row, col } | Hint::Tent { row, col }) = hint;
+        let edit = Edit {
+            row,
+            col,
+            old: self.board.get_celltype(row, col),
+            new: Self::solution_cell(solution, row, col),
+        };
+        self.hints_used += 1;
+        self.make_move(MoveKind::Hint, vec![edit]);
+        Some(hint)
+    }
+
+    /// Fill in every cell from the solution. Returns `false` if there is no solution or the board
+    /// already matches it.
+    pub fn reveal(&mut self) -> bool {
+        let Some(solution) = self.solution() else {
+            return false;
+        };
+        let edits: Vec<Edit> = self
+            .open_cells()
+            .filter_map(|(row, col)| {
+                let old = self.board.get_celltype(row, col);
+                let new = Self::solution_cell(solution, row, col);
+                (old != new).then_some(Edit { row, col, old, new })
+            })
+            .collect();
+        if edits.is_empty() {
+            return false;
+        }
+        self.make_move(MoveKind::Reveal, edits);
+        true
+    }
+
+    /// Write the game in the save file format: the time played, hints used, assists turned on and
+    /// number of moves still applied, then the puzzle, then every move including those undone, one
+    /// per line
+    pub fn to_save(&self) -> String {
+        let enabled: Vec<&str> = Assists::NAMES
+            .into_iter()
+            .zip(self.assists.flags())
+            .filter_map(|(name, on)| on.then_some(name))
+            .collect();
+        let mut lines = vec![
+            GAME_SECTION.to_string(),
+            format!("elapsed={:.3}", self.elapsed().as_secs_f64()),
+            format!("hints={}", self.hints_used),
+            format!("assists={}", enabled.join(",")),
+            format!("applied={}", self.history.len()),
+            PUZZLE_SECTION.to_string(),
+            parser::board_to_contents(&self.puzzle),
+            MOVES_SECTION.to_string(),
+        ];
+        for next in self.history.iter().chain(self.undone.iter().rev()) {
+            let edits: Vec<String> = next
+                .edits
+                .iter()
+                .map(|edit| format!("{},{},{}", edit.row, edit.col, parser::cell_code(edit.new)))
+                .collect();
+            lines.push(format!("{} {}", next.kind.name(), edits.join(" ")));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// Read a game written by `to_save`
+    pub fn from_save(contents: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::ParseError(ParseErrorType::InvalidFormat);
+        let rest = contents.strip_prefix(GAME_SECTION).ok_or_else(invalid)?;
+        let (header, rest) = rest
+            .split_once(&format!("\n{}\n", PUZZLE_SECTION))
+            .ok_or_else(invalid)?;
+        let (puzzle, moves) = rest
+            .split_once(&format!("\n{}", MOVES_SECTION))
+            .ok_or_else(invalid)?;
+
+        let mut game = Game::new(parser::get_board_from_contents(puzzle)?);
+        let mut applied = None;
+        for line in header.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let bad_option =
+                || AppError::ParseError(ParseErrorType::InvalidOption(line.trim().to_string()));
+            match key.trim() {
+                "elapsed" => {
+                    let seconds = value.trim().parse().map_err(|_| bad_option())?;
+                    game.played = Duration::try_from_secs_f64(seconds).map_err(|_| bad_option())?;
+                }
+                "hints" => game.hints_used = value.trim().parse().map_err(|_| bad_option())?,
+                "assists" => {
+                    for name in value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                    {
+                        let index = Assists::NAMES
+                            .iter()
+                            .position(|known| *known == name)
+                            .ok_or_else(bad_option)?;
+                        *game.assists.flags_mut()[index] = true;
+                    }
+                }
+                "applied" => applied = Some(value.trim().parse().map_err(|_| bad_option())?),
+                _ => return Err(bad_option()),
+            }
+        }
+
+        let lines: Vec<&str> = moves
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        for (index, line) in lines.iter().enumerate() {
+            let next = game
+                .read_move(line)
+                .ok_or(AppError::ParseError(ParseErrorType::InvalidMove(index + 1)))?;
+            for edit in &next.edits {
+                game.board.set_celltype(edit.row, edit.col, edit.new);
+            }
+            game.history.push(next);
+        }
+        let applied = applied.unwrap_or(lines.len());
+        if applied > lines.len() {
+            return Err(invalid());
+        }
+        while game.history.len() > applied {
+            game.undo();
+        }
+        game.started = Instant::now();
+        game.solved_in = None;
+        game.update_clock();
+        Ok(game)
+    }
+
+    /// Read a move from a save file, taking the old contents of each cell from the board
+    fn read_move(&self, line: &str) -> Option<Move> {
+        let mut parts = line.split_whitespace();
+        let kind = MoveKind::from_name(parts.next()?)?;
+        let mut board = self.board.clone();
+        let mut edits = Vec::new();
+        for edit in parts {
+            let mut fields = edit.split(',');
+            let row: usize = fields.next()?.parse().ok()?;
+            let col: usize = fields.next()?.parse().ok()?;
+            let new = parser::parse_cell(fields.next()?)?;
+            if fields.next().is_some()
+                || row >= board.row_count
+                || col >= board.col_count
+                || self.is_given(row, col)
+                || !matches!(new, CellType::Unknown | CellType::Empty | CellType::Tent)
+            {
+                return None;
+            }
+            let old = board.get_celltype(row, col);
+            board.set_celltype(row, col, new);
+            edits.push(Edit { row, col, old, new });
+        }
+        Some(Move { kind, edits })
+    }
+
+    pub fn save(&self, file_path: &str) -> Result<(), AppError> {
+        fs::write(file_path, self.to_save())?;
+        Ok(())
+    }
+
+    pub fn load(file_path: &str) -> Result<Self, AppError> {
+        Self::from_save(&fs::read_to_string(file_path)?)
+    }
+}
+
+/// Look for a solution to give hints from, returning `None` if there is none or the search
+/// reached the limits
+fn find_solution(puzzle: &Board, limits: &SearchLimits) -> Option<Board> {
+    let mut board = puzzle.clone();
+    board.set_mandatory_empty();
+    match backtracker::solve(TentsAndTreesConfig::new(&board), limits).outcome {
+        Outcome::Solved(solution) => Some(solution.board),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_puzzle() -> Board {
+        Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown],
+            ],
+            vec![Some(0), Some(1)],
+            vec![Some(1), Some(0)],
+        )
+    }
+
+    #[test]
+    fn moves_can_be_undone_and_redone() {
+        let mut game = Game::new(small_puzzle());
+        assert!(!game.set_cell(0, 0, CellType::Tent));
+        assert!(game.set_cell(0, 1, CellType::Empty));
+        assert!(game.set_cell(0, 1, CellType::Tent));
+        assert!(game.is_solved());
+        let solved_in = game.elapsed();
+        assert_eq!(game.elapsed(), solved_in);
+
+        assert!(game.undo());
+        assert_eq!(game.board().get_celltype(0, 1), CellType::Empty);
+        assert!(!game.is_solved());
+        assert!(game.redo());
+        assert!(!game.redo());
+        assert_eq!(game.board().get_celltype(0, 1), CellType::Tent);
+
+        game.undo();
+        game.set_cell(1, 1, CellType::Empty);
+        assert!(!game.redo());
+        assert_eq!(game.history().len(), 2);
+    }
+
+    #[test]
+    fn solution_is_found_when_first_needed() {
+        let game = Game::from_save(&Game::new(small_puzzle()).to_save()).unwrap();
+        assert!(game.solution.get().is_none());
+        assert!(game.has_solution());
+        assert_eq!(game.solution().unwrap().get_celltype(0, 1), CellType::Tent);
+
+        let game = Game::with_limits(small_puzzle(), &SearchLimits::default().with_max_nodes(0));
+        assert!(!game.has_solution());
+    }
+
+    #[test]
+    fn hints_are_counted() {
+        let mut game = Game::new(small_puzzle());
+        game.set_cell(1, 0, CellType::Tent);
+        assert_eq!(game.hint(), Some(Hint::Corrected { row: 1, col: 0 }));
+        assert_eq!(game.board().get_celltype(1, 0), CellType::Empty);
+        assert_eq!(game.hint(), Some(Hint::Tent { row: 0, col: 1 }));
+        assert_eq!(game.hint(), None);
+        assert_eq!(game.hints_used(), 2);
+        assert_eq!(game.history()[1].kind, MoveKind::Hint);
+
+        let mut game = Game::new(small_puzzle());
+        assert!(game.reveal());
+        assert!(game.is_solved());
+        assert!(!game.reveal());
+        game.undo();
+        assert_eq!(game.board(), &small_puzzle());
+    }
+
+    #[test]
+    fn assists_mark_grass_and_find_mistakes() {
+        let puzzle = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let mut game = Game::new(puzzle.clone());
+        game.assists.grass_around_tents = true;
+        game.set_cell(1, 1, CellType::Tent);
+        assert_eq!(game.board().get_celltype(0, 0), CellType::Empty);
+        assert_eq!(game.board().get_celltype(1, 2), CellType::Empty);
+        assert_eq!(game.board().get_celltype(1, 3), CellType::Unknown);
+        assert_eq!(game.history()[0].edits.len(), 7);
+        game.undo();
+        assert_eq!(game.board(), &puzzle);
+
+        game.assists = Assists {
+            fill_finished_lines: true,
+            grey_paired_trees: true,
+            ..Assists::default()
+        };
+        assert_eq!(game.paired_trees(), []);
+        game.set_cell(1, 1, CellType::Tent);
+        assert_eq!(game.board().get_celltype(1, 7), CellType::Empty);
+        assert_eq!(game.board().get_celltype(0, 0), CellType::Unknown);
+        assert_eq!(game.paired_trees(), [(0, 1)]);
+        assert_eq!(game.warnings(), []);
+
+        game.assists.warn_on_mistakes = true;
+        game.set_cell(0, 0, CellType::Tent);
+        assert_eq!(
+            game.warnings().first(),
+            Some(&Mistake::TouchingTents((0, 0), (1, 1)))
+        );
+    }
+
+    #[test]
+    fn saved_games_are_restored() {
+        let mut game = Game::new(parser::get_board_from_file("examples/example_hex.txt").unwrap());
+        game.set_cell(0, 0, CellType::Empty);
+        game.hint();
+        game.set_cell(3, 3, CellType::Tent);
+        game.undo();
+
+        game.assists.fill_finished_lines = true;
+        game.assists.warn_on_mistakes = true;
+
+        let saved = game.to_save();
+        let loaded = Game::from_save(&saved).unwrap();
+        assert_eq!(loaded.assists, game.assists);
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.history(), game.history());
+        assert_eq!(loaded.hints_used(), 1);
+        let without_time = |save: &str| {
+            let lines: Vec<&str> = save
+                .lines()
+                .filter(|line| !line.starts_with("elapsed="))
+                .collect();
+            lines.join("\n")
+        };
+        assert_eq!(without_time(&loaded.to_save()), without_time(&saved));
+
+        let elapsed_line = saved.lines().nth(1).unwrap();
+        let mut loaded = Game::from_save(&saved.replace(elapsed_line, "elapsed=90.5")).unwrap();
+        assert!(loaded.elapsed() >= Duration::from_secs_f64(90.5));
+        assert!(loaded.redo());
+        assert_eq!(loaded.board().get_celltype(3, 3), CellType::Tent);
+
+        let bad_move = saved.replace("mark 0,0,g", "mark 0,1,g");
+        assert!(matches!(
+            Game::from_save(&bad_move),
+            Err(AppError::ParseError(ParseErrorType::InvalidMove(1)))
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    backtracker::{self, Outcome, SearchLimits},
+    board::{Axis, Board, CellType, Grid, RuleSet},
+    rng::Rng,
+    solver::TentsAndTreesConfig,
+};
+
+/// One cell in this many gets a tree
+const CELLS_PER_TREE: usize = 5;
+/// How many layouts to try before giving up on finding one with a unique solution
+const MAX_ATTEMPTS: usize = 50;
+
+/// Generate a puzzle following the rules, with a clue for every line of its grid and exactly one
+/// solution. The same seed and rules always give the same puzzle. Returns `None` if no such puzzle
+/// was found, or the limits were reached first; the limits apply to each search made while
+/// checking a layout.
+pub fn generate(
+    rows: usize,
+    cols: usize,
+    rules: RuleSet,
+    seed: u64,
+    limits: &SearchLimits,
+) -> Option<Board> {
+    let mut rng = Rng::new(seed);
+    for _ in 0..MAX_ATTEMPTS {
+        let puzzle = random_layout(rows, cols, rules, &mut rng);
+        if has_unique_solution(&puzzle, limits)? {
+            return Some(puzzle);
+        }
+    }
+    None
+}
+
+/// Place tents that do not touch at random, each with a tree of its own next to it, then take the
+/// tents away leaving their counts as the clues. Which cells touch, and which are next to each
+/// other, follow the rules.
+fn random_layout(rows: usize, cols: usize, rules: RuleSet, rng: &mut Rng) -> Board {
+    let mut board = Board::new(
+        vec![vec![CellType::Unknown; cols]; rows],
+        vec![None; cols],
+        vec![None; rows],
+    );
+    board.rules = rules;
+    let target = (rows * cols / CELLS_PER_TREE).max(1);
+    let mut cells: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .collect();
+    rng.shuffle(&mut cells);
+
+    let mut tents = 0;
+    for (row, col) in cells {
+        if tents == target {
+            break;
+        }
+        if board.get_celltype(row, col) != CellType::Unknown || board.has_touching_tent(row, col) {
+            continue;
+        }
+        let spots: Vec<(usize, usize)> = rules
+            .grid
+            .directions()
+            .iter()
+            .filter_map(|direction| board.neighbour(row, col, direction))
+            .filter(|(r, c)| board.get_celltype(*r, *c) == CellType::Unknown)
+            .collect();
+        if spots.is_empty() {
+            continue;
+        }
+        let (tree_row, tree_col) = spots[rng.below(spots.len())];
+        board.set_celltype(row, col, CellType::Tent);
+        board.set_celltype(tree_row, tree_col, CellType::Tree);
+        tents += 1;
+    }
+
+    let clues = |axis, lines| {
+        (0..lines)
+            .map(|line| Some(board.line_counts(axis, line).tents))
+            .collect()
+    };
+    let cells = (0..rows)
+        .map(|row| {
+            (0..cols)
+                .map(|col| match board.get_celltype(row, col) {
+                    CellType::Tree => CellType::Tree,
+                    _ => CellType::Unknown,
+                })
+                .collect()
+        })
+        .collect();
+    let mut puzzle = Board::new(cells, clues(Axis::Column, cols), clues(Axis::Row, rows));
+    if rules.grid == Grid::Hex {
+        puzzle.set_diagonal_clues(clues(Axis::Diagonal, rows + cols - 1));
+    }
+    puzzle.rules = rules;
+    puzzle
+}
+
+/// Check that the puzzle has exactly one solution. Any other solution must leave out one of the
+/// tents of the first, so each tent is ruled out in turn and the puzzle solved again. Returns
+/// `None` if a search reached the limits.
+fn has_unique_solution(puzzle: &Board, limits: &SearchLimits) -> Option<bool> {
+    let Some(solution) = find_solution(puzzle, limits)? else {
+        return Some(false);
+    };
+    for (row, col) in solution.get_tent_positions() {
+        let mut other = puzzle.clone();
+        other.set_celltype(row, col, CellType::Empty);
+        if find_solution(&other, limits)?.is_some() {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+/// Solve the board, returning `None` if the search reached the limits
+fn find_solution(board: &Board, limits: &SearchLimits) -> Option<Option<Board>> {
+    let mut board = board.clone();
+    board.set_mandatory_empty();
+    match backtracker::solve(TentsAndTreesConfig::new(&board), limits).outcome {
+        Outcome::Solved(solution) => Some(Some(solution.board)),
+        Outcome::Unsolvable => Some(None),
+        Outcome::GaveUp(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puzzles_have_one_solution() {
+        let limits = SearchLimits::default();
+        let puzzle = generate(6, 7, RuleSet::default(), 1, &limits).unwrap();
+        assert_eq!((puzzle.row_count, puzzle.col_count), (6, 7));
+        assert_eq!(has_unique_solution(&puzzle, &limits), Some(true));
+        assert_eq!(puzzle.validate(), []);
+        let trees = puzzle.get_tree_positions().len();
+        assert!(trees >= 4, "only {} trees", trees);
+
+        let again = generate(6, 7, RuleSet::default(), 1, &limits).unwrap();
+        assert_eq!(again, puzzle);
+        assert!(again.row_clues == puzzle.row_clues);
+    }
+
+    #[test]
+    fn puzzles_follow_the_rules() {
+        let limits = SearchLimits::default();
+        for rules in [
+            RuleSet {
+                wrap_horizontal: true,
+                wrap_vertical: true,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                diagonal_tents_allowed: true,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                grid: Grid::Hex,
+                ..RuleSet::default()
+            },
+        ] {
+            let puzzle = generate(5, 6, rules, 2, &limits).unwrap();
+            assert_eq!(puzzle.rules, rules);
+            assert_eq!(has_unique_solution(&puzzle, &limits), Some(true));
+            assert_eq!(puzzle.validate(), []);
+            let diagonals = if rules.grid == Grid::Hex { 10 } else { 0 };
+            assert_eq!(puzzle.clues(Axis::Diagonal).len(), diagonals);
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use std::fmt::{Display, Write};
+
+use crate::{parser::ParseErrorType, AppError};
+
+/// How deeply arrays and objects may be nested, so that hostile input cannot overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// A JSON value. Objects keep their members in the order they were written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parse a complete JSON document
+    pub fn parse(text: &str) -> Result<Json, AppError> {
+        let mut reader = Reader {
+            bytes: text.as_bytes(),
+            pos: 0,
+        };
+        let value = reader.value(0)?;
+        reader.skip_whitespace();
+        if reader.pos != reader.bytes.len() {
+            return Err(reader.error());
+        }
+        Ok(value)
+    }
+
+    /// Build an object from its members
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Get a member of an object, or `None` if this is not an object or has no such member
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a whole number, if it is a non-negative integer small enough to be exact
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(number)
+                if *number >= 0.0 && number.fract() == 0.0 && *number <= 2f64.powi(53) =>
+            {
+                Some(*number as u64)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(text: &str) -> Self {
+        Json::String(text.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(text: String) -> Self {
+        Json::String(text)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(number: usize) -> Self {
+        Json::Number(number as f64)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(number: u64) -> Self {
+        Json::Number(number as f64)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Self {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Writes the value compactly, on a single line
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            // JSON has no infinities or NaN
+            Json::Number(number) if !number.is_finite() => f.write_str("null"),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(text) => write_string(f, text),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+                for (idx, (key, value)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Reads a JSON document by recursive descent
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self) -> AppError {
+        AppError::ParseError(ParseErrorType::InvalidJson(self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, AppError> {
+        let byte = self.peek().ok_or_else(|| self.error())?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), AppError> {
+        if !self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            return Err(self.error());
+        }
+        self.pos += word.len();
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, AppError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error());
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => self.array(depth),
+            Some(b'{') => self.object(depth),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, AppError> {
+        self.pos += 1;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => {}
+                b']' => return Ok(Json::Array(values)),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, AppError> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error());
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.next()? != b':' {
+                return Err(self.error());
+            }
+            members.push((key, self.value(depth + 1)?));
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => {}
+                b'}' => return Ok(Json::Object(members)),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, AppError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let digits = |reader: &mut Self| {
+            let from = reader.pos;
+            while matches!(reader.peek(), Some(b'0'..=b'9')) {
+                reader.pos += 1;
+            }
+            reader.pos > from
+        };
+        // JSON does not allow leading zeros, so a zero must be the whole integer part
+        let integer = self.pos;
+        if !digits(self) || (self.bytes[integer] == b'0' && self.pos - integer > 1) {
+            return Err(self.error());
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !digits(self) {
+                return Err(self.error());
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err(self.error());
+            }
+        }
+        // The bytes matched are all ASCII, so they are valid UTF-8
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+        text.parse().map(Json::Number).map_err(|_| self.error())
+    }
+
+    fn string(&mut self) -> Result<String, AppError> {
+        self.pos += 1;
+        let mut text = Vec::new();
+        loop {
+            match self.next()? {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error()),
+                    };
+                    text.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte if byte < 0x20 => return Err(self.error()),
+                byte => text.push(byte),
+            }
+        }
+        // The input was a `&str` and escapes are encoded properly, so this only fails if a string
+        // was split inside a character, which cannot happen as `"` and `\` are ASCII
+        String::from_utf8(text).map_err(|_| self.error())
+    }
+
+    /// Read the four hex digits after `\u`, and the low surrogate after them if they are a high
+    /// surrogate
+    fn unicode_escape(&mut self) -> Result<char, AppError> {
+        let first = self.hex_digits()?;
+        let code = if (0xd800..0xdc00).contains(&first) {
+            self.expect("\\u")?;
+            let second = self.hex_digits()?;
+            if !(0xdc00..0xe000).contains(&second) {
+                return Err(self.error());
+            }
+            0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or_else(|| self.error())
+    }
+
+    fn hex_digits(&mut self) -> Result<u32, AppError> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error())?;
+        self.pos += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_read_and_written() {
+        let text = r#"{"puzzle":"1,2\n.,T","seed":42,"ok":true,"list":[null,-1.5e2,"\u00e9\ud83c\udf32"]}"#;
+        let value = Json::parse(text).unwrap();
+        assert_eq!(value.get("puzzle").and_then(Json::as_str), Some("1,2\n.,T"));
+        assert_eq!(value.get("seed").and_then(Json::as_u64), Some(42));
+        assert_eq!(value.get("ok").and_then(Json::as_bool), Some(true));
+        assert_eq!(
+            value.get("list"),
+            Some(&Json::Array(vec![
+                Json::Null,
+                Json::Number(-150.0),
+                Json::from("é🌲")
+            ]))
+        );
+        assert_eq!(Json::parse(&value.to_string()).unwrap(), value);
+        for (text, number) in [("0", 0.0), ("-0.5", -0.5), ("10", 10.0), ("0e1", 0.0)] {
+            assert_eq!(Json::parse(text).unwrap(), Json::Number(number));
+        }
+        assert_eq!(
+            Json::object([
+                ("a", Json::from("\"x\"\n")),
+                ("b", Json::from(vec![1usize]))
+            ])
+            .to_string(),
+            r#"{"a":"\"x\"\n","b":[1]}"#
+        );
+    }
+
+    #[test]
+    fn bad_documents_are_rejected() {
+        for text in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01x",
+            "01",
+            "-007",
+            "\"\\q\"",
+            "[1] 2",
+            "tru",
+        ] {
+            assert!(
+                matches!(
+                    Json::parse(text),
+                    Err(AppError::ParseError(ParseErrorType::InvalidJson(_)))
+                ),
+                "{}",
+                text
+            );
+        }
+        let deep = "[".repeat(MAX_DEPTH + 2) + &"]".repeat(MAX_DEPTH + 2);
+        assert!(Json::parse(&deep).is_err());
+    }
+}
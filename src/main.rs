@@ -1,20 +1,38 @@
 use std::{fmt::Display, io};
 
+use animation::Animation;
+
 // use backtracker::Config;
+use backtracker::{Outcome, SearchResult, TranspositionTable};
 use board::BoardErrorType;
+use cli::Command;
 use parser::ParseErrorType;
 use solver::TentsAndTreesConfig;
 
-mod backtracker;
+mod animation;
+pub mod backtracker;
 pub mod board;
+mod cli;
+pub mod explain;
+pub mod game;
+pub mod generator;
+pub mod json;
 pub mod parser;
+pub mod pipe;
+pub mod play;
+pub mod render;
+pub mod rng;
+pub mod server;
 pub mod solver;
+mod terminal;
+pub mod verify;
 
 #[derive(Debug)]
 pub enum AppError {
     IoError(io::Error),
     ParseError(ParseErrorType),
     BoardError(BoardErrorType),
+    UsageError(String),
 }
 
 impl Display for AppError {
@@ -23,6 +41,7 @@ impl Display for AppError {
             AppError::IoError(e) => write!(f, "I/O error: {}", e),
             AppError::ParseError(e) => write!(f, "{}", format_args!("Parse error: {}", e)),
             AppError::BoardError(e) => write!(f, "{}", format_args!("Board error: {}", e)),
+            AppError::UsageError(e) => write!(f, "{}\n\n{}", e, cli::USAGE),
         }
     }
 }
@@ -43,12 +62,80 @@ fn main() {
 }
 
 fn run() -> Result<(), AppError> {
-    // TODO: Also seems to be duplicating possibilities.
-    let file_path = "examples/example1.txt";
+    match cli::parse_args(std::env::args().skip(1))? {
+        Command::Solve(options) => solve(options),
+        Command::Check {
+            file_path,
+            attempt: None,
+        } => check(&file_path),
+        Command::Check {
+            file_path,
+            attempt: Some(attempt),
+        } => check_attempt(&file_path, &attempt),
+        Command::Play {
+            file_path,
+            save_path,
+        } => play::run(&file_path, save_path),
+        Command::Serve(options) => server::serve(&options),
+        Command::Pipe(options) => {
+            pipe::run(io::stdin().lock(), &mut io::stdout().lock(), &options)?;
+            Ok(())
+        }
+    }
+}
+
+fn check(file_path: &str) -> Result<(), AppError> {
+    let board = parser::get_board_from_file(file_path)?;
+    let violations = board.validate();
+    if violations.is_empty() {
+        println!("No problems found in '{}'", file_path);
+        return Ok(());
+    }
+    println!("Problems found in '{}':", file_path);
+    for violation in &violations {
+        println!("  {}", violation);
+    }
+    Err(AppError::BoardError(BoardErrorType::InvalidPuzzle(
+        violations.len(),
+    )))
+}
+
+fn check_attempt(file_path: &str, attempt_path: &str) -> Result<(), AppError> {
+    let puzzle = parser::get_board_from_file(file_path)?;
+    let attempt = parser::get_board_from_file(attempt_path)?;
+    let mistakes = verify::verify(&puzzle, &attempt);
+    if !matches!(mistakes.first(), Some(verify::Mistake::WrongSize { .. })) {
+        println!("{}", verify::render_diff(&puzzle, &attempt, &mistakes));
+    }
+    if mistakes.is_empty() {
+        println!("'{}' is a correct solution", attempt_path);
+        return Ok(());
+    }
+    println!("Mistakes found in '{}':", attempt_path);
+    for mistake in &mistakes {
+        println!("  {}", mistake);
+    }
+    Err(AppError::BoardError(BoardErrorType::IncorrectSolution(
+        mistakes.len(),
+    )))
+}
+
+fn solve(options: cli::SolveOptions) -> Result<(), AppError> {
+    let file_path = &options.file_path;
     // let board = parser::get_board_from_file("examples/example_fail.txt")?;
     let mut board = parser::get_board_from_file(file_path)?;
+    if let Some(diagonal) = options.diagonal {
+        board.rules.diagonal_tents_allowed = diagonal;
+    }
+    if let Some((horizontal, vertical)) = options.wrap {
+        board.rules.wrap_horizontal = horizontal;
+        board.rules.wrap_vertical = vertical;
+    }
     println!("Initial board from file '{}':", file_path);
     println!("{}", board);
+    board.verify_given_tents()?;
+    // The clues are blamed for a failed search using the board as it was given
+    let original = board.clone();
 
     // TODO: Update the board with the easy wins - 0 clues, no trees to North, South, East or West
     board.set_mandatory_empty();
@@ -68,13 +155,48 @@ fn run() -> Result<(), AppError> {
     // }
 
     // Solve the puzzle
+    let result = search(config, &options);
     println!(
         "{}",
-        match backtracker::solve(config) {
-            Some(solution) => format!("SOLUTION FOUND:\n{}", solution),
-            None => "No solution found".to_string(),
+        match result.outcome {
+            Outcome::Solved(ref solution) => format!("SOLUTION FOUND:\n{}", solution),
+            Outcome::Unsolvable => "No solution found".to_string(),
+            Outcome::GaveUp(reason) => format!("Gave up before finding a solution: {}", reason),
         }
     );
+    if matches!(result.outcome, Outcome::Unsolvable) {
+        match explain::explain(&original, &options.limits) {
+            Some(explanation) => print!("{}", explanation),
+            None => println!("Could not find the constraints that conflict"),
+        }
+    }
+    if options.stats {
+        println!("Search statistics:");
+        println!("{}", result.report);
+    }
 
     Ok(())
 }
+
+/// Run the search chosen by the command line options
+fn search(
+    config: TentsAndTreesConfig,
+    options: &cli::SolveOptions,
+) -> SearchResult<TentsAndTreesConfig> {
+    let limits = &options.limits;
+    let table = &mut TranspositionTable::default();
+    match (options.seed, options.animate, options.in_place) {
+        (Some(seed), _, _) => match &options.restarts {
+            Some(policy) => backtracker::solve_with_restarts(config, limits, seed, policy),
+            None => backtracker::solve_shuffled(config, limits, seed),
+        },
+        (None, Some(delay), false) => {
+            backtracker::solve_observed(config, limits, table, &mut Animation::new(delay))
+        }
+        (None, Some(delay), true) => {
+            backtracker::solve_in_place_observed(config, limits, table, &mut Animation::new(delay))
+        }
+        (None, None, false) => backtracker::solve_with_table(config, limits, table),
+        (None, None, true) => backtracker::solve_in_place_observed(config, limits, table, &mut ()),
+    }
+}
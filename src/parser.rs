@@ -1,7 +1,7 @@
 use std::{fmt::Display, fs::File, io::Read};
 
 use crate::{
-    board::{Board, CellType},
+    board::{Axis, Board, CellType, Grid, RuleSet},
     AppError,
 };
 
@@ -14,8 +14,17 @@ pub enum ParseErrorType {
     EmptyRowClues,
     InvalidClueLength(usize),
     InvalidRowLength(usize),
-    InvalidBoardLength(usize),
+    /// The board has a different number of rows to the row clues: (rows, clues)
+    InvalidBoardLength(usize, usize),
     InvalidFormat,
+    InvalidOption(String),
+    MissingDiagonalClues,
+    MissingRegionMap,
+    UnknownRegion(char),
+    /// A move in a save file, numbered from 1, that is not valid for the saved puzzle
+    InvalidMove(usize),
+    /// A JSON document could not be read, at the given byte offset
+    InvalidJson(usize),
 }
 
 impl Display for ParseErrorType {
@@ -34,10 +43,41 @@ impl Display for ParseErrorType {
                 "Board representation row {} does not have enough fields",
                 row_num
             ),
-            ParseErrorType::InvalidBoardLength(rows) => {
-                write!(f, "Board representation only has {} rows", rows)
+            &ParseErrorType::InvalidBoardLength(rows, clues) => {
+                write!(
+                    f,
+                    "Board representation has {} rows but there are {} row clues: ",
+                    rows, clues
+                )?;
+                let (first, last) = (rows.min(clues), rows.max(clues) - 1);
+                let lines = if first == last {
+                    format!("row {} is", first)
+                } else {
+                    format!("rows {} to {} are", first, last)
+                };
+                if rows < clues {
+                    write!(f, "{} missing", lines)
+                } else {
+                    write!(f, "{} past the last clue", lines)
+                }
             }
             ParseErrorType::InvalidFormat => write!(f, "Invalid Format"),
+            ParseErrorType::InvalidOption(option) => write!(f, "Invalid option: {}", option),
+            ParseErrorType::MissingDiagonalClues => {
+                write!(f, "Diagonal clues are not present for a hex board")
+            }
+            ParseErrorType::MissingRegionMap => write!(f, "The region map is not present"),
+            ParseErrorType::UnknownRegion(region) => {
+                write!(f, "Region '{}' does not have a clue", region)
+            }
+            ParseErrorType::InvalidMove(number) => {
+                write!(
+                    f,
+                    "Move {} in the save file does not fit the puzzle",
+                    number
+                )
+            }
+            ParseErrorType::InvalidJson(offset) => write!(f, "Invalid JSON at byte {}", offset),
         }
     }
 }
@@ -50,7 +90,50 @@ pub fn get_board_from_file(file_path: &str) -> Result<Board, AppError> {
     get_board_from_contents(&contents)
 }
 
-fn get_board_from_contents(contents: &str) -> Result<Board, AppError> {
+/// The option that records whether tents may touch diagonally, e.g. `diagonal-tents=allowed`
+pub const DIAGONAL_TENTS_OPTION: &str = "diagonal-tents";
+/// The option that records which edges of the board wrap around, e.g. `wrap=horizontal`
+pub const WRAP_OPTION: &str = "wrap";
+/// The option that records the shape of the cells, e.g. `grid=hex`. Hex boards have a line of
+/// diagonal clues after the row clues.
+pub const GRID_OPTION: &str = "grid";
+/// The option that gives the clues for the regions of the board, e.g. `regions=2,.,1`. The
+/// region map follows the board, with a letter for the region of each cell, `a` for the first
+/// clue, `b` for the second and so on, or `.` for a cell outside every region.
+pub const REGIONS_OPTION: &str = "regions";
+
+/// Read a board from the contents of a puzzle file
+pub fn get_board_from_contents(contents: &str) -> Result<Board, AppError> {
+    let (options, contents) = split_options(contents);
+    let rules = parse_rules(&options)?;
+    let region_clues = options
+        .iter()
+        .find(|(key, _)| *key == REGIONS_OPTION)
+        .map(|(_, clues)| get_clues(clues, clues.split(",").count()))
+        .transpose()?;
+
+    // Take out the diagonal clues and region map, leaving the layout of a square board
+    let mut lines: Vec<&str> = contents.lines().collect();
+    let diagonal_clues = match rules.grid {
+        Grid::Square => None,
+        Grid::Hex if lines.len() < 3 => {
+            return Err(AppError::ParseError(ParseErrorType::MissingDiagonalClues))
+        }
+        Grid::Hex => Some(lines.remove(2)),
+    };
+    let region_map = match region_clues {
+        Some(_) => {
+            let rows = lines.get(1).map_or(0, |line| line.split(",").count());
+            if lines.len() < rows + 2 {
+                return Err(AppError::ParseError(ParseErrorType::MissingRegionMap));
+            }
+            lines.split_off(lines.len() - rows)
+        }
+        None => Vec::new(),
+    };
+    let contents = lines.join("\n");
+    let contents = contents.as_str();
+
     let (col_qty, row_qty) = parse_metadata(contents)?;
     let mut lines = contents.lines();
     let col_clues = get_clues(lines.next().unwrap(), col_qty)?;
@@ -59,7 +142,59 @@ fn get_board_from_contents(contents: &str) -> Result<Board, AppError> {
     for row in lines {
         board.push(get_board_row(row, col_qty)?);
     }
-    Ok(Board::new(board, col_clues, row_clues))
+    let mut board = Board::new(board, col_clues, row_clues);
+    if let Some(diagonal_clues) = diagonal_clues {
+        board.set_diagonal_clues(get_clues(diagonal_clues, col_qty + row_qty - 1)?);
+    }
+    if let Some(region_clues) = region_clues {
+        let regions = region_map
+            .iter()
+            .map(|row| get_region_row(row, col_qty, region_clues.len()))
+            .collect::<Result<_, _>>()?;
+        board.set_regions(regions, region_clues)?;
+    }
+    board.rules = rules;
+    Ok(board)
+}
+
+/// Split off the `key=value` option lines at the start of the contents, returning the options
+/// and the rest of the contents
+fn split_options(contents: &str) -> (Vec<(&str, &str)>, &str) {
+    let mut options = Vec::new();
+    let mut rest = contents;
+    while let Some((key, value)) = rest.lines().next().and_then(|line| line.split_once('=')) {
+        options.push((key.trim(), value.trim()));
+        rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+    }
+    (options, rest)
+}
+
+fn parse_rules(options: &[(&str, &str)]) -> Result<RuleSet, AppError> {
+    let mut rules = RuleSet::default();
+    for (key, value) in options {
+        match (*key, *value) {
+            (DIAGONAL_TENTS_OPTION, diagonal) => match parse_diagonal(diagonal) {
+                Some(allowed) => rules.diagonal_tents_allowed = allowed,
+                None => return Err(invalid_option(key, value)),
+            },
+            // The region clues are read along with the region map
+            (REGIONS_OPTION, _) => {}
+            (GRID_OPTION, grid) => match parse_grid(grid) {
+                Some(grid) => rules.grid = grid,
+                None => return Err(invalid_option(key, value)),
+            },
+            (WRAP_OPTION, wrap) => match parse_wrap(wrap) {
+                Some(axes) => (rules.wrap_horizontal, rules.wrap_vertical) = axes,
+                None => return Err(invalid_option(key, value)),
+            },
+            _ => return Err(invalid_option(key, value)),
+        }
+    }
+    Ok(rules)
+}
+
+fn invalid_option(key: &str, value: &str) -> AppError {
+    AppError::ParseError(ParseErrorType::InvalidOption(format!("{}={}", key, value)))
 }
 
 fn parse_metadata(contents: &str) -> Result<(usize, usize), AppError> {
@@ -94,19 +229,46 @@ fn parse_metadata(contents: &str) -> Result<(usize, usize), AppError> {
     if board_rows != row_clues_len {
         return Err(AppError::ParseError(ParseErrorType::InvalidBoardLength(
             board_rows,
+            row_clues_len,
         )));
     }
 
     Ok((col_clues_len, row_clues_len))
 }
 
+/// Parse whether tents may touch diagonally, `allowed` or `forbidden`
+pub fn parse_diagonal(diagonal: &str) -> Option<bool> {
+    match diagonal {
+        "allowed" => Some(true),
+        "forbidden" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse the shape of the cells, `square` or `hex`
+pub fn parse_grid(grid: &str) -> Option<Grid> {
+    match grid {
+        "square" => Some(Grid::Square),
+        "hex" => Some(Grid::Hex),
+        _ => None,
+    }
+}
+
+/// Parse which edges wrap around, as (horizontal, vertical)
+pub fn parse_wrap(wrap: &str) -> Option<(bool, bool)> {
+    match wrap {
+        "none" => Some((false, false)),
+        "horizontal" => Some((true, false)),
+        "vertical" => Some((false, true)),
+        "both" => Some((true, true)),
+        _ => None,
+    }
+}
+
 fn get_clues(line: &str, expected_len: usize) -> Result<Vec<Option<usize>>, AppError> {
     let clues = line.split(",").map(|val| val.trim());
     let mut result = Vec::new();
     for clue in clues {
-        if clue.len() != 1 {
-            return Err(AppError::ParseError(ParseErrorType::InvalidFormat));
-        }
         if clue == "." || clue == "_" {
             result.push(None);
             continue;
@@ -132,14 +294,62 @@ fn get_board_row(row_str: &str, expected_len: usize) -> Result<Vec<CellType>, Ap
         if value.len() != 1 {
             return Err(AppError::ParseError(ParseErrorType::InvalidFormat));
         }
-        let cell_type = match value {
-            "." | "_" => CellType::Unknown,
-            "t" | "T" => CellType::Tree,
-            "x" | "X" => CellType::Tent,
-            "u" | "U" => CellType::Unknown,
+        let cell_type =
+            parse_cell(value).ok_or(AppError::ParseError(ParseErrorType::InvalidFormat))?;
+        result.push(cell_type)
+    }
+    if result.len() != expected_len {
+        return Err(AppError::ParseError(ParseErrorType::InvalidRowLength(
+            result.len(),
+        )));
+    }
+    Ok(result)
+}
+
+/// Read the code for a single cell
+pub fn parse_cell(value: &str) -> Option<CellType> {
+    match value {
+        "." | "_" => Some(CellType::Unknown),
+        "t" | "T" => Some(CellType::Tree),
+        "x" | "X" => Some(CellType::Tent),
+        "e" | "E" | "g" | "G" => Some(CellType::Empty),
+        "u" | "U" => Some(CellType::Unknown),
+        "#" => Some(CellType::Blocked),
+        _ => None,
+    }
+}
+
+/// Get the code written for a cell, which `parse_cell` reads back
+pub fn cell_code(cell: CellType) -> &'static str {
+    match cell {
+        CellType::Unknown => ".",
+        CellType::Empty => "g",
+        CellType::Tent => "x",
+        CellType::Tree => "t",
+        CellType::Blocked => "#",
+    }
+}
+
+fn get_region_row(
+    row_str: &str,
+    expected_len: usize,
+    region_qty: usize,
+) -> Result<Vec<Option<usize>>, AppError> {
+    let mut result = Vec::new();
+    for value in row_str.split(",").map(|val| val.trim()) {
+        let mut chars = value.chars();
+        let region = match (chars.next(), chars.next()) {
+            (Some('.'), None) => None,
+            (Some(region @ 'a'..='z'), None) => {
+                let region_idx = region as usize - 'a' as usize;
+                if region_idx >= region_qty {
+                    return Err(AppError::ParseError(ParseErrorType::UnknownRegion(region)));
+                }
+                Some(region_idx)
+            }
             _ => return Err(AppError::ParseError(ParseErrorType::InvalidFormat)),
         };
-        result.push(cell_type)
+        result.push(region);
     }
     if result.len() != expected_len {
         return Err(AppError::ParseError(ParseErrorType::InvalidRowLength(
@@ -149,10 +359,71 @@ fn get_board_row(row_str: &str, expected_len: usize) -> Result<Vec<CellType>, Ap
     Ok(result)
 }
 
+/// Write a board in the format read by `get_board_from_contents`, including its rules, so that
+/// reading it back gives the same puzzle
+pub fn board_to_contents(board: &Board) -> String {
+    let mut lines = Vec::new();
+    if board.rules.diagonal_tents_allowed {
+        lines.push(format!("{}=allowed", DIAGONAL_TENTS_OPTION));
+    }
+    let wrap = match (board.rules.wrap_horizontal, board.rules.wrap_vertical) {
+        (false, false) => None,
+        (true, false) => Some("horizontal"),
+        (false, true) => Some("vertical"),
+        (true, true) => Some("both"),
+    };
+    if let Some(wrap) = wrap {
+        lines.push(format!("{}={}", WRAP_OPTION, wrap));
+    }
+    if board.rules.grid == Grid::Hex {
+        lines.push(format!("{}=hex", GRID_OPTION));
+    }
+    if !board.region_clues.is_empty() {
+        lines.push(format!(
+            "{}={}",
+            REGIONS_OPTION,
+            clues_line(board, Axis::Region)
+        ));
+    }
+    lines.push(clues_line(board, Axis::Column));
+    lines.push(clues_line(board, Axis::Row));
+    if board.rules.grid == Grid::Hex {
+        lines.push(clues_line(board, Axis::Diagonal));
+    }
+    for row in 0..board.row_count {
+        let cells: Vec<&str> = (0..board.col_count)
+            .map(|col| cell_code(board.get_celltype(row, col)))
+            .collect();
+        lines.push(cells.join(","));
+    }
+    if !board.region_clues.is_empty() {
+        for row in 0..board.row_count {
+            let regions: Vec<String> = (0..board.col_count)
+                .map(|col| match board.region(row, col) {
+                    Some(region) => ((b'a' + region as u8) as char).to_string(),
+                    None => ".".to_string(),
+                })
+                .collect();
+            lines.push(regions.join(","));
+        }
+    }
+    lines.join("\n")
+}
+
+fn clues_line(board: &Board, axis: Axis) -> String {
+    let clues: Vec<String> = (0..board.clues(axis).len())
+        .map(|line| match board.clue(axis, line) {
+            Some(clue) => clue.to_string(),
+            None => ".".to_string(),
+        })
+        .collect();
+    clues.join(",")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::board::Board;
+    use crate::board::{Axis, Board, BoardErrorType};
 
     #[test]
     fn file_not_found() {
@@ -191,7 +462,9 @@ mod tests {
         let result = parse_metadata("1,2,3\n4,5,6");
         assert!(matches!(
             result,
-            Err(AppError::ParseError(ParseErrorType::InvalidBoardLength(0)))
+            Err(AppError::ParseError(ParseErrorType::InvalidBoardLength(
+                0, 3
+            )))
         ));
     }
 
@@ -246,8 +519,18 @@ T,.,.";
         let result = parse_metadata(failed_contents);
         assert!(matches!(
             result,
-            Err(AppError::ParseError(ParseErrorType::InvalidBoardLength(2)))
+            Err(AppError::ParseError(ParseErrorType::InvalidBoardLength(
+                2, 3
+            )))
         ));
+        assert_eq!(
+            ParseErrorType::InvalidBoardLength(2, 3).to_string(),
+            "Board representation has 2 rows but there are 3 row clues: row 2 is missing"
+        );
+        assert_eq!(
+            ParseErrorType::InvalidBoardLength(5, 3).to_string(),
+            "Board representation has 5 rows but there are 3 row clues: rows 3 to 4 are past the last clue"
+        );
     }
 
     #[test]
@@ -273,4 +556,198 @@ T,.,.
         let result = result.unwrap();
         assert_eq!(result, expected_board);
     }
+
+    #[test]
+    fn rectangular_board() {
+        let result = get_board_from_file("examples/example_5x8.txt").unwrap();
+        assert_eq!((result.row_count, result.col_count), (5, 8));
+        assert_eq!(result.get_celltype(4, 6), CellType::Tree);
+        let result = get_board_from_file("examples/example_12x7.txt").unwrap();
+        assert_eq!((result.row_count, result.col_count), (12, 7));
+        assert_eq!(result.get_celltype(11, 3), CellType::Tree);
+    }
+
+    #[test]
+    fn two_digit_clues() {
+        let row_clues = vec!["1"; 22].join(",");
+        let rows = vec![".,t,."; 22].join("\n");
+        let contents = format!("11,.,0\n{}\n{}", row_clues, rows);
+        let result = get_board_from_contents(&contents).unwrap();
+        assert_eq!((result.row_count, result.col_count), (22, 3));
+        assert_eq!(result.clue(Axis::Column, 0), Some(11));
+        assert_eq!(result.clue(Axis::Row, 21), Some(1));
+        assert_eq!(board_to_contents(&result), contents);
+
+        let result = get_board_from_contents(&contents.replace("11,", "1a,"));
+        assert!(matches!(
+            result,
+            Err(AppError::ParseError(ParseErrorType::InvalidFormat))
+        ));
+    }
+
+    #[test]
+    fn blocked_cells() {
+        let row = get_board_row("#,T,.,#", 4).unwrap();
+        assert_eq!(
+            row,
+            [
+                CellType::Blocked,
+                CellType::Tree,
+                CellType::Unknown,
+                CellType::Blocked
+            ]
+        );
+    }
+
+    #[test]
+    fn grass_marks() {
+        let row = get_board_row("E,g,X,T", 4).unwrap();
+        assert_eq!(
+            row,
+            [
+                CellType::Empty,
+                CellType::Empty,
+                CellType::Tent,
+                CellType::Tree
+            ]
+        );
+    }
+
+    #[test]
+    fn written_boards_read_back() {
+        for example in [
+            "example1",
+            "example_hex",
+            "example_regions",
+            "example_given",
+            "example_wrap",
+            "example_12x7",
+        ] {
+            let board = get_board_from_file(&format!("examples/{}.txt", example)).unwrap();
+            let contents = board_to_contents(&board);
+            let read_back = get_board_from_contents(&contents).unwrap();
+            assert_eq!(read_back, board, "{}", example);
+            assert_eq!(read_back.rules, board.rules, "{}", example);
+            assert_eq!(board_to_contents(&read_back), contents, "{}", example);
+        }
+    }
+
+    #[test]
+    fn default_rules() {
+        let result = get_board_from_contents("1,.\n.,1\nT,.\n.,.").unwrap();
+        assert_eq!(result.rules, RuleSet::default());
+
+        let result = get_board_from_contents("diagonal-tents=forbidden\n1,.\n.,1\nT,.\n.,.");
+        assert_eq!(result.unwrap().rules, RuleSet::default());
+        let result = get_board_from_contents("diagonal-tents=maybe\n1,.\n.,1\nT,.\n.,.");
+        assert!(matches!(
+            result,
+            Err(AppError::ParseError(ParseErrorType::InvalidOption(_)))
+        ));
+    }
+
+    #[test]
+    fn rule_options() {
+        let contents = "diagonal-tents=allowed
+1,1
+1,1
+T,.
+.,T";
+        let result = get_board_from_contents(contents).unwrap();
+        assert!(result.rules.diagonal_tents_allowed);
+        assert_eq!(result.row_count, 2);
+
+        let contents = contents.replace("diagonal-tents=allowed", "wrap=vertical");
+        let result = get_board_from_contents(&contents).unwrap();
+        assert!(!result.rules.diagonal_tents_allowed);
+        assert!(result.rules.wrap_vertical);
+        assert!(!result.rules.wrap_horizontal);
+
+        let result = get_board_from_contents(&contents.replace("vertical", "sometimes"));
+        assert!(matches!(
+            result,
+            Err(AppError::ParseError(ParseErrorType::InvalidOption(_)))
+        ));
+    }
+
+    #[test]
+    fn hex_grid() {
+        let contents = "grid=hex
+1,.
+1,0
+0,1,.
+T,.
+.,.";
+        let result = get_board_from_contents(contents).unwrap();
+        assert_eq!(result.rules.grid, Grid::Hex);
+        assert_eq!(result.row_count, 2);
+        assert_eq!(result.clue(Axis::Diagonal, 0), Some(0));
+        assert_eq!(result.clue(Axis::Diagonal, 1), Some(1));
+        assert_eq!(result.clue(Axis::Diagonal, 2), None);
+
+        let result = get_board_from_contents(&contents.replace("0,1,.", "0,1"));
+        assert!(matches!(
+            result,
+            Err(AppError::ParseError(ParseErrorType::InvalidRowLength(2)))
+        ));
+        let result = get_board_from_contents("grid=hex\n1,.\n1,0");
+        assert!(matches!(
+            result,
+            Err(AppError::ParseError(ParseErrorType::MissingDiagonalClues))
+        ));
+    }
+
+    #[test]
+    fn every_region_letter_reads_back() {
+        let mut board = Board::new(
+            vec![vec![CellType::Unknown; 13]; 2],
+            vec![None; 13],
+            vec![None; 2],
+        );
+        let regions: Vec<Vec<Option<usize>>> = (0..2)
+            .map(|row| (0..13).map(|col| Some(row * 13 + col)).collect())
+            .collect();
+        board.set_regions(regions, vec![Some(1); 26]).unwrap();
+        let contents = board_to_contents(&board);
+        assert!(contents.ends_with("n,o,p,q,r,s,t,u,v,w,x,y,z"));
+        let read_back = get_board_from_contents(&contents).unwrap();
+        assert_eq!(read_back.region(1, 12), Some(25));
+        assert_eq!(board_to_contents(&read_back), contents);
+
+        let mut regions = vec![vec![Some(0); 13]; 2];
+        regions[1][12] = Some(26);
+        assert!(matches!(
+            board.set_regions(regions, vec![Some(1); 27]),
+            Err(AppError::BoardError(BoardErrorType::InvalidRegion(26)))
+        ));
+    }
+
+    #[test]
+    fn region_map() {
+        let contents = "regions=1,.
+.,.
+.,.
+T,.
+.,.
+a,a
+.,b";
+        let result = get_board_from_contents(contents).unwrap();
+        assert_eq!(result.row_count, 2);
+        assert_eq!(result.region(0, 1), Some(0));
+        assert_eq!(result.region(1, 0), None);
+        assert_eq!(result.region(1, 1), Some(1));
+        assert_eq!(result.clue(Axis::Region, 0), Some(1));
+        assert_eq!(result.clue(Axis::Region, 1), None);
+
+        let result = get_board_from_contents(&contents.replace(".,b", ".,c"));
+        assert!(matches!(
+            result,
+            Err(AppError::ParseError(ParseErrorType::UnknownRegion('c')))
+        ));
+        let result = get_board_from_contents("regions=1\n.,.\n.");
+        assert!(matches!(
+            result,
+            Err(AppError::ParseError(ParseErrorType::MissingRegionMap))
+        ));
+    }
 }
@@ -0,0 +1,233 @@
+use std::{
+    io::{self, BufRead, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    time::Duration,
+};
+
+use crate::{backtracker::SearchLimits, json::Json, server};
+
+/// Separates the lines of a puzzle written on a single input line
+const ROW_SEPARATOR: char = ';';
+
+/// Settings for the pipe mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipeOptions {
+    /// How long the searches for a single input line may run
+    pub solve_timeout: Duration,
+    /// The longest input line accepted; longer lines are skipped with an error
+    pub max_line_bytes: usize,
+}
+
+impl Default for PipeOptions {
+    fn default() -> Self {
+        PipeOptions {
+            solve_timeout: Duration::from_secs(10),
+            max_line_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// Answer every line of the input with a line of JSON, until the input ends. A line is either a
+/// puzzle to solve, with its lines separated by `;`, or a JSON object as accepted by the HTTP
+/// service, with an optional `"action"` naming the endpoint (`"solve"` by default) and an
+/// optional `"id"` that is copied to the result. Each result has the `"line"` number it answers,
+/// counting from 1. Blank lines are skipped. A line that cannot be answered gets a result with an
+/// `"error"`; only a failure to read the input or write the output stops the loop.
+pub fn run<R: BufRead, W: Write>(
+    mut input: R,
+    output: &mut W,
+    options: &PipeOptions,
+) -> io::Result<()> {
+    let mut line_number = 0;
+    loop {
+        let mut line = Vec::new();
+        let read = (&mut input)
+            .take(options.max_line_bytes as u64 + 1)
+            .read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        line_number += 1;
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        } else if line.len() > options.max_line_bytes {
+            skip_line(&mut input)?;
+            let error = format!("Lines are limited to {} bytes", options.max_line_bytes);
+            write_result(output, line_number, None, error_result(error))?;
+            continue;
+        }
+        let Ok(line) = String::from_utf8(line) else {
+            write_result(
+                output,
+                line_number,
+                None,
+                error_result("The line is not UTF-8"),
+            )?;
+            continue;
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // A panic while answering one line must not lose the answers to the rest
+        let (id, result) = panic::catch_unwind(AssertUnwindSafe(|| answer(line, options)))
+            .unwrap_or_else(|_| {
+                (
+                    None,
+                    error_result("Internal error while answering the line"),
+                )
+            });
+        write_result(output, line_number, id, result)?;
+    }
+}
+
+/// Discard the rest of a line that was too long
+fn skip_line<R: BufRead>(input: &mut R) -> io::Result<()> {
+    loop {
+        let buffer = input.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        match buffer.iter().position(|byte| *byte == b'\n') {
+            Some(end) => {
+                input.consume(end + 1);
+                return Ok(());
+            }
+            None => {
+                let len = buffer.len();
+                input.consume(len);
+            }
+        }
+    }
+}
+
+/// Answer a line, returning its id if it had one, and the members of the result
+fn answer(line: &str, options: &PipeOptions) -> (Option<Json>, Json) {
+    let limits = SearchLimits::default().with_timeout(options.solve_timeout);
+    if !line.starts_with('{') {
+        let puzzle = line.replace(ROW_SEPARATOR, "\n");
+        let body = Json::object([("puzzle", puzzle.into())]);
+        let result = match server::respond("solve", &body, &limits) {
+            Ok(Json::Object(members)) => Json::Object(
+                members
+                    .into_iter()
+                    .map(|(name, value)| match value {
+                        Json::String(text) if name == "solution" => {
+                            (name, text.replace('\n', &ROW_SEPARATOR.to_string()).into())
+                        }
+                        value => (name, value),
+                    })
+                    .collect(),
+            ),
+            Ok(result) => result,
+            Err(response) => response.body,
+        };
+        return (None, result);
+    }
+
+    let body = match Json::parse(line) {
+        Ok(body @ Json::Object(_)) => body,
+        Ok(_) => return (None, error_result("The line must be a JSON object")),
+        Err(e) => return (None, error_result(e.to_string())),
+    };
+    let id = body.get("id").cloned();
+    let result = match body.get("action").map(Json::as_str) {
+        None => server::respond("solve", &body, &limits),
+        Some(Some(action)) => server::respond(action, &body, &limits),
+        Some(None) => return (id, error_result("'action' must be a string")),
+    };
+    (id, result.unwrap_or_else(|response| response.body))
+}
+
+fn error_result(message: impl Into<String>) -> Json {
+    Json::object([("error", Json::String(message.into()))])
+}
+
+/// Write a result on a line of its own, after the line number and id it answers, and flush it so
+/// that whatever reads the output gets it straight away
+fn write_result<W: Write>(
+    output: &mut W,
+    line_number: usize,
+    id: Option<Json>,
+    result: Json,
+) -> io::Result<()> {
+    let mut members = vec![("line".to_string(), Json::from(line_number))];
+    if let Some(id) = id {
+        members.push(("id".to_string(), id));
+    }
+    if let Json::Object(result) = result {
+        members.extend(result);
+    }
+    writeln!(output, "{}", Json::Object(members))?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_lines(input: &str, options: &PipeOptions) -> Vec<Json> {
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output, options).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| Json::parse(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn every_line_gets_a_result() {
+        let example = std::fs::read_to_string("examples/example1.txt").unwrap();
+        let puzzle = example.trim().replace('\n', ";");
+        let object = Json::object([
+            ("id", Json::from("first")),
+            ("action", Json::from("validate")),
+            ("puzzle", Json::from(example.as_str())),
+        ]);
+        let input = format!(
+            "{}\n\nnot a puzzle\n{{\"puzzle\": 3\n{}\n{{\"action\":\"fly\"}}\n{}",
+            puzzle, object, puzzle
+        );
+        let results = run_lines(&input, &PipeOptions::default());
+        assert_eq!(results.len(), 6);
+
+        let first = &results[0];
+        assert_eq!(first.get("line").and_then(Json::as_u64), Some(1));
+        assert_eq!(first.get("status"), Some(&Json::from("solved")));
+        let solution = first.get("solution").and_then(Json::as_str).unwrap();
+        assert_eq!(solution.split(';').count(), 10);
+
+        assert_eq!(results[1].get("line").and_then(Json::as_u64), Some(3));
+        assert!(results[1].get("error").is_some());
+        assert!(results[2].get("error").is_some());
+        assert_eq!(results[3].get("id"), Some(&Json::from("first")));
+        assert_eq!(results[3].get("valid"), Some(&Json::Bool(true)));
+        assert_eq!(
+            results[4].get("error"),
+            Some(&Json::from("There is no endpoint fly"))
+        );
+        assert_eq!(results[5].get("line").and_then(Json::as_u64), Some(7));
+        assert_eq!(results[5].get("status"), Some(&Json::from("solved")));
+    }
+
+    #[test]
+    fn long_lines_are_skipped() {
+        let options = PipeOptions {
+            max_line_bytes: 10,
+            ..PipeOptions::default()
+        };
+        let input = format!("{}\n{{}}\n", "x".repeat(100));
+        let results = run_lines(&input, &options);
+        assert_eq!(
+            results[0].get("error"),
+            Some(&Json::from("Lines are limited to 10 bytes"))
+        );
+        assert_eq!(results[1].get("line").and_then(Json::as_u64), Some(2));
+        assert_eq!(
+            results[1].get("error"),
+            Some(&Json::from("'puzzle' is missing"))
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::{io, path::Path};
+
+use crate::{
+    board::{Axis, CellType},
+    game::{Assists, Game, Hint},
+    parser,
+    render::{self, Highlight},
+    terminal::{Key, Keys, RawTerminal},
+    AppError,
+};
+
+const HELP: &str = "Arrows/hjkl move  Space cycle  x tent  g grass  c clear  \
+u undo  r redo  ? hint  S reveal  1-4 assists  w save  q quit";
+
+const CURSOR_STYLE: &str = "\x1b[7m";
+const CLUE_MET_STYLE: &str = "\x1b[32m";
+const CLUE_EXCEEDED_STYLE: &str = "\x1b[31m";
+const PAIRED_TREE_STYLE: &str = "\x1b[90m";
+
+/// A game being played in the terminal
+pub struct Play {
+    game: Game,
+    cursor: (usize, usize),
+    message: String,
+    // Where the game is saved to
+    save_path: String,
+}
+
+impl Play {
+    pub fn new(game: Game, save_path: String) -> Self {
+        Play {
+            game,
+            cursor: (0, 0),
+            message: String::new(),
+            save_path,
+        }
+    }
+
+    /// Respond to a key press. Returns `false` once the player has asked to quit.
+    pub fn handle(&mut self, key: Key) -> bool {
+        self.message.clear();
+        let (row, col) = self.cursor;
+        let board = self.game.board();
+        match key {
+            Key::Up | Key::Char('k') => self.cursor.0 = row.saturating_sub(1),
+            Key::Down | Key::Char('j') => self.cursor.0 = (row + 1).min(board.row_count - 1),
+            Key::Left | Key::Char('h') => self.cursor.1 = col.saturating_sub(1),
+            Key::Right | Key::Char('l') => self.cursor.1 = (col + 1).min(board.col_count - 1),
+            Key::Char(' ') | Key::Enter => {
+                let next = match board.get_celltype(row, col) {
+                    CellType::Unknown => CellType::Empty,
+                    CellType::Empty => CellType::Tent,
+                    _ => CellType::Unknown,
+                };
+                self.set_cell(next);
+            }
+            Key::Char('x') | Key::Char('t') => self.set_cell(CellType::Tent),
+            Key::Char('g') | Key::Char('e') => self.set_cell(CellType::Empty),
+            Key::Char('c') | Key::Backspace => self.set_cell(CellType::Unknown),
+            Key::Char('u') => {
+                if self.game.undo() {
+                    self.check_solved();
+                } else {
+                    self.message = "Nothing to undo".to_string();
+                }
+            }
+            Key::Char('r') => {
+                if self.game.redo() {
+                    self.check_solved();
+                } else {
+                    self.message = "Nothing to redo".to_string();
+                }
+            }
+            Key::Char('?') => self.hint(),
+            Key::Char('S') => {
+                if !self.game.has_solution() {
+                    self.message = "No solution was found to reveal".to_string();
+                } else if self.game.reveal() {
+                    self.check_solved();
+                }
+            }
+            Key::Char(digit @ '1'..='4') => {
+                let index = digit as usize - '1' as usize;
+                let flag = &mut self.game.assists.flags_mut()[index];
+                **flag = !**flag;
+                let state = if **flag { "on" } else { "off" };
+                self.message = format!("Turned {} {}", Assists::NAMES[index], state);
+            }
+            Key::Char('w') => {
+                self.message = match self.game.save(&self.save_path) {
+                    Ok(()) => format!("Saved to '{}'", self.save_path),
+                    Err(e) => format!("Could not save: {}", e),
+                };
+            }
+            Key::Char('q') | Key::Char('\x03') => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn set_cell(&mut self, cell: CellType) {
+        let (row, col) = self.cursor;
+        if self.game.is_given(row, col) {
+            self.message = "That cell is part of the puzzle".to_string();
+        } else if self.game.set_cell(row, col, cell) {
+            self.check_solved();
+        }
+    }
+
+    fn hint(&mut self) {
+        if !self.game.has_solution() {
+            self.message = "No solution was found, so hints are not available".to_string();
+            return;
+        }
+        match self.game.hint() {
+            Some(hint) => {
+                let (Hint::Corrected { row, col } | Hint::Tent { row, col }) = hint;
+                self.cursor = (row, col);
+                self.message = hint.to_string();
+                self.check_solved();
+            }
+            None => self.message = "There is nothing left to hint".to_string(),
+        }
+    }
+
+    fn check_solved(&mut self) {
+        if self.game.is_solved() {
+            self.message = "Solved! Press q to quit".to_string();
+        }
+    }
+
+    /// Draw the screen: the board with the cursor, touching tents, the cells of any warnings,
+    /// paired trees and the state of each clue highlighted, then the time, hints used and
+    /// assists, the first warning, the message and the keys
+    pub fn render(&self) -> String {
+        let board = self.game.board();
+        let warnings = self.game.warnings();
+        let wrong: Vec<(usize, usize)> = warnings
+            .iter()
+            .flat_map(|warning| warning.cells(self.game.puzzle(), board))
+            .collect();
+        let paired_trees = self.game.paired_trees();
+        let grid = render::render_styled(
+            board,
+            |row, col| {
+                let cell = board.get_celltype(row, col);
+                if (row, col) == self.cursor {
+                    CURSOR_STYLE
+                } else if wrong.contains(&(row, col))
+                    || (cell == CellType::Tent && board.has_touching_tent(row, col))
+                {
+                    Highlight::Removed.style()
+                } else if paired_trees.contains(&(row, col)) {
+                    PAIRED_TREE_STYLE
+                } else {
+                    render::cell_style(cell)
+                }
+            },
+            |axis, line| self.clue_style(axis, line),
+        );
+
+        let seconds = self.game.elapsed().as_secs();
+        let assists: Vec<String> = Assists::NAMES
+            .iter()
+            .zip(self.game.assists.flags())
+            .enumerate()
+            .map(|(index, (name, on))| {
+                format!("[{}] {} {}", if on { 'x' } else { ' ' }, index + 1, name)
+            })
+            .collect();
+        let warning = match warnings.len() {
+            0 => String::new(),
+            1 => format!("Warning: {}", warnings[0]),
+            count => format!("Warning: {} (and {} more)", warnings[0], count - 1),
+        };
+        format!(
+            "{}Tents and Trees\n\n{}\nTime {}:{:02}  Hints {}  Assists {}\n{}\n{}\n{}\n",
+            render::REDRAW,
+            grid,
+            seconds / 60,
+            seconds % 60,
+            self.game.hints_used(),
+            assists.join("  "),
+            warning,
+            self.message,
+            HELP
+        )
+    }
+
+    fn clue_style(&self, axis: Axis, line: usize) -> &'static str {
+        let board = self.game.board();
+        let Some(clue) = board.clue(axis, line) else {
+            return "";
+        };
+        let tents = board.line_counts(axis, line).tents;
+        if tents > clue {
+            CLUE_EXCEEDED_STYLE
+        } else if tents == clue {
+            CLUE_MET_STYLE
+        } else {
+            ""
+        }
+    }
+}
+
+/// Play the puzzle in the given file in the terminal, until the player quits. The game is saved
+/// to the save file, which defaults to the puzzle file with `.save` added, and resumed from it if
+/// it already exists.
+pub fn run(file_path: &str, save_path: Option<String>) -> Result<(), AppError> {
+    let puzzle = parser::get_board_from_file(file_path)?;
+    puzzle.verify_given_tents()?;
+    let save_path = save_path.unwrap_or_else(|| format!("{}.save", file_path));
+    let resumed = Path::new(&save_path).exists();
+    let game = match resumed {
+        true => {
+            let game = Game::load(&save_path)?;
+            if parser::board_to_contents(game.puzzle()) != parser::board_to_contents(&puzzle) {
+                return Err(AppError::UsageError(format!(
+                    "'{}' is a saved game of a different puzzle",
+                    save_path
+                )));
+            }
+            game
+        }
+        false => Game::new(puzzle),
+    };
+    let mut play = Play::new(game, save_path);
+    if resumed {
+        play.message = format!("Resumed the game saved in '{}'", play.save_path);
+    }
+
+    let terminal = RawTerminal::enable()?;
+    let keys = Keys::new(io::stdin());
+    terminal.draw(&play.render())?;
+    while let Some(key) = keys.read_key()? {
+        if !play.handle(key) {
+            break;
+        }
+        terminal.draw(&play.render())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    fn small_puzzle() -> Play {
+        let puzzle = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown],
+                vec![CellType::Unknown, CellType::Unknown],
+            ],
+            vec![Some(0), Some(1)],
+            vec![Some(1), Some(0)],
+        );
+        Play::new(Game::new(puzzle), "small.save".to_string())
+    }
+
+    #[test]
+    fn cells_cycle_with_undo_and_redo() {
+        let mut play = small_puzzle();
+        play.handle(Key::Right);
+        play.handle(Key::Char(' '));
+        assert_eq!(play.game.board().get_celltype(0, 1), CellType::Empty);
+        play.handle(Key::Char(' '));
+        assert_eq!(play.game.board().get_celltype(0, 1), CellType::Tent);
+        assert_eq!(play.message, "Solved! Press q to quit");
+
+        play.handle(Key::Char('u'));
+        assert_eq!(play.game.board().get_celltype(0, 1), CellType::Empty);
+        play.handle(Key::Char('r'));
+        assert_eq!(play.game.board().get_celltype(0, 1), CellType::Tent);
+        play.handle(Key::Char('r'));
+        assert_eq!(play.message, "Nothing to redo");
+
+        play.handle(Key::Left);
+        play.handle(Key::Char('x'));
+        assert_eq!(play.game.board().get_celltype(0, 0), CellType::Tree);
+        assert_eq!(play.message, "That cell is part of the puzzle");
+        assert!(!play.handle(Key::Char('q')));
+    }
+
+    #[test]
+    fn hints_move_the_cursor() {
+        let mut play = small_puzzle();
+        play.handle(Key::Down);
+        play.handle(Key::Char('x'));
+        play.handle(Key::Char('?'));
+        assert_eq!(play.game.board().get_celltype(1, 0), CellType::Empty);
+        assert_eq!(play.message, "The cell at (1, 0) was wrong");
+        play.handle(Key::Char('?'));
+        assert_eq!(play.game.board().get_celltype(0, 1), CellType::Tent);
+        assert_eq!(play.cursor, (0, 1));
+        assert_eq!(play.message, "Solved! Press q to quit");
+    }
+
+    #[test]
+    fn assists_are_toggled() {
+        let mut play = small_puzzle();
+        play.handle(Key::Char('4'));
+        assert_eq!(play.message, "Turned warnings on");
+        play.handle(Key::Down);
+        play.handle(Key::Right);
+        play.handle(Key::Char('x'));
+        let screen = play.render();
+        assert!(screen.contains("[x] 4 warnings"));
+        assert!(
+            screen.contains("Warning: There are 1 tents in row 1 but its clue is 0 (and 1 more)")
+        );
+        play.handle(Key::Char('4'));
+        assert!(!play.render().contains("Warning:"));
+    }
+
+    #[test]
+    fn screen_shows_cursor_and_clues() {
+        let mut play = small_puzzle();
+        play.handle(Key::Right);
+        play.handle(Key::Char('x'));
+        let screen = play.render();
+        assert!(screen.contains(&format!("{} X ", CURSOR_STYLE)));
+        assert!(screen.contains(&format!("{} 1", CLUE_MET_STYLE)));
+        assert!(screen.contains("Time 0:00  Hints 0  Assists [ ] 1 grass"));
+        assert!(screen.ends_with(&format!("{}\n", HELP)));
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt::Write;
+
+use crate::board::{Axis, Board, CellType, Grid};
+
+/// ANSI escape sequence to clear the terminal and move the cursor to the top left
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+/// ANSI escape sequence to move the cursor to the top left and clear everything below it, so
+/// that a frame can be drawn over the previous one without flickering
+pub const REDRAW: &str = "\x1b[H\x1b[J";
+
+const RESET: &str = "\x1b[0m";
+
+/// Ways of drawing attention to a cell when rendering a board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    /// The cell has just been filled in
+    Added,
+    /// The cell is about to be cleared again, or is part of a mistake
+    Removed,
+}
+
+impl Highlight {
+    pub fn style(&self) -> &'static str {
+        match self {
+            Highlight::Added => "\x1b[30;42m",
+            Highlight::Removed => "\x1b[30;41m",
+        }
+    }
+}
+
+pub fn cell_style(cell: CellType) -> &'static str {
+    match cell {
+        CellType::Unknown => "",
+        CellType::Empty => "\x1b[2m",
+        CellType::Tent => "\x1b[1;33m",
+        CellType::Tree => "\x1b[32m",
+        CellType::Blocked => "\x1b[90m",
+    }
+}
+
+/// Get the character a cell is drawn with
+pub fn cell_symbol(cell: CellType) -> char {
+    match cell {
+        CellType::Empty => 'E',
+        CellType::Unknown => '-',
+        CellType::Tent => 'X',
+        CellType::Tree => 'T',
+        CellType::Blocked => '#',
+    }
+}
+
+/// Lay out a hex board, with every row shifted half a cell to the right of the one above and the
+/// diagonal clues on a line of their own underneath. The `cell` and `clue` functions give the
+/// text to draw each cell and clue with, which should be one character wide once printed.
+pub fn hex_layout<F, L>(board: &Board, cell: F, clue: L) -> String
+where
+    F: Fn(usize, usize) -> String,
+    L: Fn(Axis, usize) -> String,
+{
+    let mut out = String::from("   |");
+    for col_num in 0..board.col_clues.len() {
+        write!(out, " {}", clue(Axis::Column, col_num)).unwrap();
+    }
+    out.push('\n');
+    out.push_str("---|");
+    out.push_str(&"-".repeat(2 * board.col_count + board.row_count.saturating_sub(1)));
+    out.push('\n');
+    for row_num in 0..board.row_clues.len() {
+        write!(
+            out,
+            " {} |{}",
+            clue(Axis::Row, row_num),
+            " ".repeat(row_num)
+        )
+        .unwrap();
+        for col_num in 0..board.col_count {
+            write!(out, " {}", cell(row_num, col_num)).unwrap();
+        }
+        out.push('\n');
+    }
+    out.push_str(" / |");
+    for line in 0..board.diagonal_clues.len() {
+        write!(out, " {}", clue(Axis::Diagonal, line)).unwrap();
+    }
+    out.push('\n');
+    out
+}
+
+/// Get the plain text of a clue, without any styling
+pub fn clue_text(board: &Board, axis: Axis, line: usize) -> String {
+    board
+        .clue(axis, line)
+        .map_or_else(|| "_".to_string(), |val| val.to_string())
+}
+
+/// Render the board with ANSI colours, in the same layout as its `Display` implementation. The
+/// `highlight` function is called for every cell, and can pick a highlight to draw it with.
+pub fn render_board<F>(board: &Board, highlight: F) -> String
+where
+    F: Fn(usize, usize) -> Option<Highlight>,
+{
+    render_styled(
+        board,
+        |row, col| match highlight(row, col) {
+            Some(highlight) => highlight.style(),
+            None => cell_style(board.get_celltype(row, col)),
+        },
+        |_, _| "",
+    )
+}
+
+/// Render the board with ANSI colours, in the same layout as its `Display` implementation, with
+/// the escape sequences to style each cell and clue given by `cell_style` and `clue_style`
+pub fn render_styled<C, L>(board: &Board, cell_style: C, clue_style: L) -> String
+where
+    C: Fn(usize, usize) -> &'static str,
+    L: Fn(Axis, usize) -> &'static str,
+{
+    // Clues are only reset after they are styled, so that plain clues are drawn as plain text
+    let clue = |axis, line, width| {
+        let text = clue_text(board, axis, line);
+        match clue_style(axis, line) {
+            "" => format!("{:>1$}", text, width),
+            style => format!("{}{:>3$}{}", style, text, RESET, width),
+        }
+    };
+    if board.rules.grid == Grid::Hex {
+        return hex_layout(
+            board,
+            |row, col| {
+                let symbol = cell_symbol(board.get_celltype(row, col));
+                format!("{}{}{}", cell_style(row, col), symbol, RESET)
+            },
+            |axis, line| clue(axis, line, 1),
+        );
+    }
+
+    let mut out = String::new();
+    out.push_str("   |");
+    for col_num in 0..board.col_clues.len() {
+        write!(out, "{} ", clue(Axis::Column, col_num, 2)).unwrap();
+    }
+    out.push('\n');
+    out.push_str("---|");
+    out.push_str(&"---".repeat(board.col_clues.len()));
+    out.push('\n');
+    for row_num in 0..board.row_clues.len() {
+        write!(out, "{} |", clue(Axis::Row, row_num, 2)).unwrap();
+        for col_num in 0..board.col_clues.len() {
+            let symbol = cell_symbol(board.get_celltype(row_num, col_num));
+            write!(out, "{} {} {}", cell_style(row_num, col_num), symbol, RESET).unwrap();
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_rows_are_shifted() {
+        let mut board = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Unknown],
+                vec![CellType::Tent, CellType::Empty],
+            ],
+            vec![Some(1), None],
+            vec![Some(0), Some(1)],
+        );
+        board.rules.grid = Grid::Hex;
+        board.set_diagonal_clues(vec![Some(0), Some(1), None]);
+        let layout = hex_layout(
+            &board,
+            |row, col| cell_symbol(board.get_celltype(row, col)).to_string(),
+            |axis, line| clue_text(&board, axis, line),
+        );
+        assert_eq!(
+            layout,
+            "   | 1 _\n---|-----\n 0 | T -\n 1 |  X E\n / | 0 1 _\n"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+/// Mix the bits of a value with the SplitMix64 finaliser. Nearby inputs give unrelated outputs,
+/// so this can be used to derive pseudo-random values from counters or positions.
+pub fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A small seeded pseudo-random number generator (SplitMix64). The same seed always produces the
+/// same sequence, so randomised searches can be reproduced. Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let value = split_mix(self.state);
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        value
+    }
+
+    /// Get a number in the range `0..bound`, which must not be empty
+    pub fn below(&mut self, bound: usize) -> usize {
+        let bound = bound as u64;
+        // Reject the values that would make the lower numbers more likely than the higher ones
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % bound) as usize;
+            }
+        }
+    }
+
+    /// Shuffle the items into a random order (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            let other = self.below(idx + 1);
+            items.swap(idx, other);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+        let mut other = Rng::new(43);
+        let first_values: Vec<u64> = (0..5).map(|_| first.next_u64()).collect();
+        let second_values: Vec<u64> = (0..5).map(|_| second.next_u64()).collect();
+        let other_values: Vec<u64> = (0..5).map(|_| other.next_u64()).collect();
+        assert_eq!(first_values, second_values);
+        assert_ne!(first_values, other_values);
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let mut rng = Rng::new(7);
+        let mut items: Vec<usize> = (0..20).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+        assert!((0..100).all(|_| rng.below(3) < 3));
+    }
+}
@@ -0,0 +1,745 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    backtracker::{self, Outcome, SearchLimits},
+    board::{Board, RuleSet},
+    explain,
+    game::{Game, Hint},
+    generator,
+    json::Json,
+    parser, render,
+    solver::TentsAndTreesConfig,
+    verify::{self, Mistake},
+    AppError,
+};
+
+/// The service only listens on the loopback interface
+const ADDRESS: &str = "127.0.0.1";
+/// How long to wait for a client to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// The largest number of rows or columns of a generated puzzle, so that checking a layout has a
+/// unique solution finishes in time
+const MAX_GENERATED_SIZE: u64 = 16;
+
+/// Settings for the HTTP service
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerOptions {
+    pub port: u16,
+    /// The largest request accepted, counting the request line and headers as well as the body
+    pub max_request_bytes: usize,
+    /// How long the searches made for a single request may run
+    pub solve_timeout: Duration,
+    /// The most connections handled at once. Any more are answered straight away with a 503.
+    pub max_connections: usize,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            port: 8080,
+            max_request_bytes: 64 * 1024,
+            solve_timeout: Duration::from_secs(10),
+            max_connections: 4,
+        }
+    }
+}
+
+/// A request read from a client. Only the parts used by the endpoints are kept.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// The path, without any query string
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// A response to send to a client. Every response, including errors, has a JSON body.
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Response {
+    fn ok(body: Json) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response {
+            status,
+            body: Json::object([("error", Json::String(message.into()))]),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            413 => "Content Too Large",
+            422 => "Unprocessable Content",
+            501 => "Not Implemented",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let body = self.body.to_string();
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            body.len(),
+            body
+        )?;
+        out.flush()
+    }
+}
+
+/// Serve requests on localhost until the process is stopped. Each connection is handled on its
+/// own thread, up to the limit on connections, and closed after a single response.
+pub fn serve(options: &ServerOptions) -> Result<(), AppError> {
+    let listener = TcpListener::bind((ADDRESS, options.port))?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    run(listener, options, Arc::new(AtomicUsize::new(0)));
+    Ok(())
+}
+
+/// Handle connections from the listener, counting the ones being handled in `active`
+fn run(listener: TcpListener, options: &ServerOptions, active: Arc<AtomicUsize>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let Some(slot) = ConnectionSlot::take(&active, options.max_connections) else {
+                    let response = Response::error(503, "The server is busy, try again later");
+                    let _ = response.write_to(&mut &stream);
+                    continue;
+                };
+                let options = options.clone();
+                thread::spawn(move || {
+                    handle_connection(stream, &options);
+                    drop(slot);
+                });
+            }
+            Err(e) => eprintln!("Could not accept a connection: {}", e),
+        }
+    }
+}
+
+/// A place among the connections being handled, given back when it is dropped, so that it is
+/// given back even if handling the connection panics
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// Take a place, unless `max` connections are already being handled
+    fn take(active: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < max).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| ConnectionSlot(active.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn handle_connection(stream: TcpStream, options: &ServerOptions) {
+    let response = match stream.set_read_timeout(Some(READ_TIMEOUT)) {
+        Ok(()) => match read_request(&mut BufReader::new(&stream), options.max_request_bytes) {
+            Ok(request) => handle(&request, options),
+            Err(response) => response,
+        },
+        Err(e) => Response::error(500, e.to_string()),
+    };
+    // Errors are ignored, as the client has gone away and there is no one left to tell
+    let _ = response.write_to(&mut &stream);
+}
+
+/// Read an HTTP/1.1 request with a body of `Content-Length` bytes, returning the error response
+/// to send if it cannot be read or is larger than the limit
+pub fn read_request<R: BufRead>(input: &mut R, max_bytes: usize) -> Result<Request, Response> {
+    let read_error = |e: io::Error| match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+            Response::error(408, "The request was not sent in time")
+        }
+        _ => Response::error(400, e.to_string()),
+    };
+    let too_large = || Response::error(413, format!("Requests are limited to {} bytes", max_bytes));
+
+    // Read one byte past the limit, to tell a request that fits exactly from one that is too large
+    let mut input = input.take(max_bytes as u64 + 1);
+    let mut head = Vec::new();
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(read_error)? == 0 {
+            return Err(match input.limit() {
+                0 => too_large(),
+                _ => Response::error(400, "The request ended before its headers"),
+            });
+        }
+        if line.trim_end().is_empty() {
+            break;
+        }
+        head.push(line.trim_end().to_string());
+    }
+    if input.limit() == 0 {
+        return Err(too_large());
+    }
+
+    let request_line = head.first().map(String::as_str).unwrap_or("");
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(Response::error(400, "The request line is not valid"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(Response::error(400, "Only HTTP/1.x is supported"));
+    }
+
+    let mut content_length = 0;
+    for header in &head[1..] {
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(Response::error(400, "A header is not valid"));
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .trim()
+                .parse()
+                .map_err(|_| Response::error(400, "Content-Length is not a number"))?;
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(Response::error(
+                501,
+                "Only requests with a Content-Length are supported",
+            ));
+        }
+    }
+    if content_length >= input.limit() as usize {
+        return Err(too_large());
+    }
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body).map_err(read_error)?;
+
+    let path = target.split('?').next().unwrap_or(target);
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body,
+    })
+}
+
+/// The function answering requests to an endpoint, with the body of the request
+type Endpoint = fn(&Json, &SearchLimits) -> Result<Json, Response>;
+
+/// Get the endpoint with the given name, which is its path without the leading `/`
+fn endpoint(name: &str) -> Option<Endpoint> {
+    match name {
+        "solve" => Some(solve),
+        "validate" => Some(validate),
+        "hint" => Some(hint),
+        "generate" => Some(generate),
+        "render" => Some(render),
+        _ => None,
+    }
+}
+
+/// Answer a request to the named endpoint, with its JSON body, as the service would. Returns the
+/// error response if the endpoint does not exist or cannot answer.
+pub fn respond(name: &str, body: &Json, limits: &SearchLimits) -> Result<Json, Response> {
+    let endpoint = endpoint(name)
+        .ok_or_else(|| Response::error(404, format!("There is no endpoint {}", name)))?;
+    endpoint(body, limits)
+}
+
+/// Respond to a request. Every endpoint takes a POST with a JSON object as its body.
+pub fn handle(request: &Request, options: &ServerOptions) -> Response {
+    let Some(endpoint) = request.path.strip_prefix('/').and_then(endpoint) else {
+        return Response::error(404, format!("There is no endpoint {}", request.path));
+    };
+    if request.method != "POST" {
+        return Response::error(405, "Endpoints only accept POST requests");
+    }
+    let body = match std::str::from_utf8(&request.body) {
+        Ok(body) => body,
+        Err(_) => return Response::error(400, "The body is not UTF-8"),
+    };
+    let body = match Json::parse(body) {
+        Ok(body @ Json::Object(_)) => body,
+        Ok(_) => return Response::error(400, "The body must be a JSON object"),
+        Err(e) => return Response::error(400, e.to_string()),
+    };
+    // The deadline is fixed here, so it covers every search made for the request
+    let limits = SearchLimits::default().with_timeout(options.solve_timeout);
+    match endpoint(&body, &limits) {
+        Ok(body) => Response::ok(body),
+        Err(response) => response,
+    }
+}
+
+/// Read a board from a string member of the request, in the format of a puzzle file
+fn board_field(body: &Json, name: &str) -> Result<Option<Board>, Response> {
+    let Some(value) = body.get(name) else {
+        return Ok(None);
+    };
+    let contents = value
+        .as_str()
+        .ok_or_else(|| Response::error(400, format!("'{}' must be a string", name)))?;
+    parser::get_board_from_contents(contents)
+        .map(Some)
+        .map_err(|e| Response::error(422, format!("'{}': {}", name, e)))
+}
+
+fn puzzle_field(body: &Json) -> Result<Board, Response> {
+    board_field(body, "puzzle")?.ok_or_else(|| Response::error(400, "'puzzle' is missing"))
+}
+
+fn positions(cells: &[(usize, usize)]) -> Json {
+    Json::Array(
+        cells
+            .iter()
+            .map(|(row, col)| Json::from(vec![*row, *col]))
+            .collect(),
+    )
+}
+
+/// `{"puzzle"}` to `{"status": "solved", "solution", "tents", "nodes"}`, or `"unsolvable"` with
+/// an `"explanation"` if one was found, or `"gave_up"` with the `"reason"`
+fn solve(body: &Json, limits: &SearchLimits) -> Result<Json, Response> {
+    let puzzle = puzzle_field(body)?;
+    puzzle
+        .verify_given_tents()
+        .map_err(|e| Response::error(422, e.to_string()))?;
+    let mut board = puzzle.clone();
+    board.set_mandatory_empty();
+    let result = backtracker::solve(TentsAndTreesConfig::new(&board), limits);
+    let nodes = Json::from(result.report.nodes_expanded);
+    Ok(match result.outcome {
+        Outcome::Solved(solution) => Json::object([
+            ("status", Json::from("solved")),
+            (
+                "solution",
+                parser::board_to_contents(&solution.board).into(),
+            ),
+            ("tents", positions(&solution.board.get_tent_positions())),
+            ("nodes", nodes),
+        ]),
+        Outcome::Unsolvable => Json::object([
+            ("status", Json::from("unsolvable")),
+            (
+                "explanation",
+                explain::explain(&puzzle, limits)
+                    .map(|explanation| explanation.to_string())
+                    .into(),
+            ),
+            ("nodes", nodes),
+        ]),
+        Outcome::GaveUp(reason) => Json::object([
+            ("status", Json::from("gave_up")),
+            ("reason", reason.to_string().into()),
+            ("nodes", nodes),
+        ]),
+    })
+}
+
+/// `{"puzzle"}` to `{"valid", "problems"}`, or with an `"attempt"`, to `{"correct", "mistakes"}`
+fn validate(body: &Json, _limits: &SearchLimits) -> Result<Json, Response> {
+    let puzzle = puzzle_field(body)?;
+    Ok(match board_field(body, "attempt")? {
+        None => {
+            let problems: Vec<String> = puzzle.validate().iter().map(|v| v.to_string()).collect();
+            Json::object([
+                ("valid", problems.is_empty().into()),
+                ("problems", problems.into()),
+            ])
+        }
+        Some(attempt) => {
+            let mistakes: Vec<String> = verify::verify(&puzzle, &attempt)
+                .iter()
+                .map(|mistake| mistake.to_string())
+                .collect();
+            Json::object([
+                ("correct", mistakes.is_empty().into()),
+                ("mistakes", mistakes.into()),
+            ])
+        }
+    })
+}
+
+/// `{"puzzle", "board"}`, where the board is the player's progress, to `{"hint", "board"}`. The
+/// hint has the `"kind"` (`"corrected"` or `"tent"`), `"row"`, `"col"` and new `"cell"`, or is
+/// null if there is nothing left to do.
+fn hint(body: &Json, limits: &SearchLimits) -> Result<Json, Response> {
+    let puzzle = puzzle_field(body)?;
+    let progress = board_field(body, "board")?;
+    let mut game = Game::with_limits(puzzle.clone(), limits);
+    if !game.has_solution() {
+        return Err(Response::error(
+            422,
+            "No solution to give hints from was found",
+        ));
+    }
+    if let Some(progress) = progress {
+        let changed = verify::verify(&puzzle, &progress)
+            .into_iter()
+            .find(|mistake| {
+                matches!(
+                    mistake,
+                    Mistake::WrongSize { .. } | Mistake::ChangedCell { .. }
+                )
+            });
+        if let Some(mistake) = changed {
+            return Err(Response::error(422, format!("'board': {}", mistake)));
+        }
+        for row in 0..puzzle.row_count {
+            for col in 0..puzzle.col_count {
+                game.set_cell(row, col, progress.get_celltype(row, col));
+            }
+        }
+    }
+
+    let hint = game.hint().map(|hint| {
+        let (kind, row, col) = match hint {
+            Hint::Corrected { row, col } => ("corrected", row, col),
+            Hint::Tent { row, col } => ("tent", row, col),
+        };
+        let cell = game.board().get_celltype(row, col);
+        Json::object([
+            ("kind", kind.into()),
+            ("row", row.into()),
+            ("col", col.into()),
+            ("cell", parser::cell_code(cell).into()),
+            ("message", hint.to_string().into()),
+        ])
+    });
+    Ok(Json::object([
+        ("hint", hint.into()),
+        ("board", parser::board_to_contents(game.board()).into()),
+    ]))
+}
+
+/// Read an optional string member, which must be one of the values read by `parse`
+fn option_field<T>(
+    body: &Json,
+    name: &str,
+    values: &str,
+    parse: fn(&str) -> Option<T>,
+) -> Result<Option<T>, Response> {
+    body.get(name)
+        .map(|value| {
+            value.as_str().and_then(parse).ok_or_else(|| {
+                Response::error(400, format!("'{}' must be one of {}", name, values))
+            })
+        })
+        .transpose()
+}
+
+/// Read the rules for a puzzle to generate from the optional `"diagonal"`, `"wrap"` and `"grid"`
+/// members, which take the same values as the options of a puzzle file
+fn rules_field(body: &Json) -> Result<RuleSet, Response> {
+    let mut rules = RuleSet::default();
+    let diagonal = option_field(
+        body,
+        "diagonal",
+        "allowed or forbidden",
+        parser::parse_diagonal,
+    )?;
+    if let Some(diagonal) = diagonal {
+        rules.diagonal_tents_allowed = diagonal;
+    }
+    let wrap = option_field(
+        body,
+        "wrap",
+        "none, horizontal, vertical or both",
+        parser::parse_wrap,
+    )?;
+    if let Some(wrap) = wrap {
+        (rules.wrap_horizontal, rules.wrap_vertical) = wrap;
+    }
+    if let Some(grid) = option_field(body, "grid", "square or hex", parser::parse_grid)? {
+        rules.grid = grid;
+    }
+    Ok(rules)
+}
+
+/// `{"rows", "cols", "seed", "diagonal", "wrap", "grid"}`, where all but the size are optional,
+/// to `{"puzzle", "seed"}`
+fn generate(body: &Json, limits: &SearchLimits) -> Result<Json, Response> {
+    let size = |name: &str| {
+        body.get(name)
+            .and_then(Json::as_u64)
+            .filter(|size| (2..=MAX_GENERATED_SIZE).contains(size))
+            .map(|size| size as usize)
+            .ok_or_else(|| {
+                Response::error(
+                    400,
+                    format!(
+                        "'{}' must be a whole number from 2 to {}",
+                        name, MAX_GENERATED_SIZE
+                    ),
+                )
+            })
+    };
+    let (rows, cols) = (size("rows")?, size("cols")?);
+    let rules = rules_field(body)?;
+    let seed = match body.get("seed") {
+        Some(seed) => seed
+            .as_u64()
+            .ok_or_else(|| Response::error(400, "'seed' must be a whole number"))?,
+        // Seeds are kept below 2^53, so they can be sent back as exact JSON numbers
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64 % (1 << 53)),
+    };
+    let puzzle = generator::generate(rows, cols, rules, seed, limits).ok_or_else(|| {
+        Response::error(503, "No puzzle with a unique solution was found in time")
+    })?;
+    Ok(Json::object([
+        ("puzzle", parser::board_to_contents(&puzzle).into()),
+        ("seed", seed.into()),
+    ]))
+}
+
+/// `{"puzzle", "color"}` to `{"text"}`, the board drawn as by the solver, with ANSI colours if
+/// `"color"` is true
+fn render(body: &Json, _limits: &SearchLimits) -> Result<Json, Response> {
+    let puzzle = puzzle_field(body)?;
+    let text = match body.get("color").and_then(Json::as_bool) {
+        Some(true) => render::render_board(&puzzle, |_, _| None),
+        _ => puzzle.to_string(),
+    };
+    Ok(Json::object([("text", text.into())]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(path: &str, body: Json) -> Response {
+        let request = Request {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            body: body.to_string().into_bytes(),
+        };
+        handle(&request, &ServerOptions::default())
+    }
+
+    fn example(name: &str) -> Json {
+        let contents = std::fs::read_to_string(format!("examples/{}.txt", name)).unwrap();
+        Json::from(contents)
+    }
+
+    #[test]
+    fn requests_are_read_within_the_limit() {
+        let text = "POST /solve?x=1 HTTP/1.1\r\nHost: localhost\r\ncontent-length: 4\r\n\r\n{}\r\n";
+        let request = read_request(&mut text.as_bytes(), 1000).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/solve");
+        assert_eq!(request.body, b"{}\r\n");
+        assert!(read_request(&mut text.as_bytes(), text.len()).is_ok());
+
+        let status = |text: &str, limit| {
+            read_request(&mut text.as_bytes(), limit)
+                .unwrap_err()
+                .status
+        };
+        assert_eq!(status(text, text.len() - 1), 413);
+        assert_eq!(status(text, 20), 413);
+        assert_eq!(status("GET /\r\n\r\n", 100), 400);
+        assert_eq!(status("POST / HTTP/1.1\r\nHost", 100), 400);
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n", 100),
+            501
+        );
+    }
+
+    #[test]
+    fn puzzles_are_solved() {
+        let response = post("/solve", Json::object([("puzzle", example("example1"))]));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body.get("status"), Some(&Json::from("solved")));
+        let solution = response
+            .body
+            .get("solution")
+            .and_then(Json::as_str)
+            .unwrap();
+        let puzzle = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let solution = parser::get_board_from_contents(solution).unwrap();
+        assert_eq!(verify::verify(&puzzle, &solution), []);
+
+        let response = post(
+            "/solve",
+            Json::object([("puzzle", example("example_unsolvable"))]),
+        );
+        assert_eq!(response.body.get("status"), Some(&Json::from("unsolvable")));
+        assert!(response.body.get("explanation").unwrap().as_str().is_some());
+    }
+
+    #[test]
+    fn bad_requests_get_errors() {
+        assert_eq!(post("/nowhere", Json::Null).status, 404);
+        assert_eq!(post("/solve", Json::Array(Vec::new())).status, 400);
+        assert_eq!(post("/solve", Json::object([])).status, 400);
+        let bad_puzzle = Json::object([("puzzle", Json::from("1,2\n3"))]);
+        assert_eq!(post("/solve", bad_puzzle).status, 422);
+
+        let get = Request {
+            method: "GET".to_string(),
+            path: "/solve".to_string(),
+            body: Vec::new(),
+        };
+        assert_eq!(handle(&get, &ServerOptions::default()).status, 405);
+        let bad_json = Request {
+            method: "POST".to_string(),
+            path: "/solve".to_string(),
+            body: b"{\"puzzle\":".to_vec(),
+        };
+        let response = handle(&bad_json, &ServerOptions::default());
+        assert_eq!(response.status, 400);
+        assert_eq!(
+            response.body.get("error"),
+            Some(&Json::from("Parse error: Invalid JSON at byte 10"))
+        );
+    }
+
+    #[test]
+    fn generated_puzzles_can_be_played() {
+        let body = Json::object([
+            ("rows", Json::from(6usize)),
+            ("cols", Json::from(5usize)),
+            ("seed", Json::from(3usize)),
+        ]);
+        let response = post("/generate", body);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body.get("seed").and_then(Json::as_u64), Some(3));
+        let puzzle = response.body.get("puzzle").unwrap().clone();
+
+        let response = post("/validate", Json::object([("puzzle", puzzle.clone())]));
+        assert_eq!(response.body.get("valid"), Some(&Json::Bool(true)));
+
+        let response = post("/hint", Json::object([("puzzle", puzzle.clone())]));
+        let hint = response.body.get("hint").unwrap();
+        assert_eq!(hint.get("kind"), Some(&Json::from("tent")));
+        assert_eq!(hint.get("cell"), Some(&Json::from("x")));
+        let board = response.body.get("board").unwrap().clone();
+        let response = post(
+            "/hint",
+            Json::object([("puzzle", puzzle.clone()), ("board", board)]),
+        );
+        assert_eq!(response.status, 200);
+        assert_ne!(response.body.get("hint"), Some(&hint.clone()));
+
+        let response = post("/render", Json::object([("puzzle", puzzle)]));
+        let text = response.body.get("text").and_then(Json::as_str).unwrap();
+        assert_eq!(text.lines().count(), 6 + 2);
+
+        let too_big = Json::object([("rows", Json::from(40usize)), ("cols", Json::from(5usize))]);
+        assert_eq!(post("/generate", too_big).status, 400);
+    }
+
+    #[test]
+    fn generated_puzzles_follow_the_rules() {
+        let body = |grid: &str| {
+            Json::object([
+                ("rows", Json::from(5usize)),
+                ("cols", Json::from(5usize)),
+                ("seed", Json::from(4usize)),
+                ("wrap", Json::from("both")),
+                ("grid", Json::from(grid)),
+            ])
+        };
+        let response = post("/generate", body("square"));
+        assert_eq!(response.status, 200);
+        let puzzle = response.body.get("puzzle").and_then(Json::as_str).unwrap();
+        let puzzle = parser::get_board_from_contents(puzzle).unwrap();
+        assert!(puzzle.rules.wrap_horizontal && puzzle.rules.wrap_vertical);
+
+        let response = post("/generate", body("triangle"));
+        assert_eq!(response.status, 400);
+        assert_eq!(
+            response.body.get("error"),
+            Some(&Json::from("'grid' must be one of square or hex"))
+        );
+    }
+
+    #[test]
+    fn responses_are_sent_over_tcp() {
+        let listener = TcpListener::bind((ADDRESS, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            run(
+                listener,
+                &ServerOptions::default(),
+                Arc::new(AtomicUsize::new(0)),
+            )
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let body = Json::object([("puzzle", example("example1"))]).to_string();
+        write!(
+            stream,
+            "POST /render HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(Json::parse(body).unwrap().get("text").is_some());
+    }
+
+    #[test]
+    fn connections_past_the_limit_are_turned_away() {
+        let listener = TcpListener::bind((ADDRESS, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let options = ServerOptions {
+            max_connections: 2,
+            ..ServerOptions::default()
+        };
+        let active = Arc::new(AtomicUsize::new(0));
+        thread::spawn({
+            let active = active.clone();
+            move || run(listener, &options, active)
+        });
+
+        // Every place is taken here, so the server has none left to give
+        let slots: Vec<ConnectionSlot> = (0..2)
+            .map(|_| ConnectionSlot::take(&active, 2).unwrap())
+            .collect();
+        assert!(ConnectionSlot::take(&active, 2).is_none());
+        let mut turned_away = TcpStream::connect(address).unwrap();
+        let mut response = String::new();
+        turned_away.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+
+        // Dropping the slots gives their places back
+        drop(slots);
+        assert_eq!(active.load(Ordering::SeqCst), 0);
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"POST /none HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
@@ -1,129 +1,376 @@
-use std::{
-    cmp::{max, min},
-    fmt,
-};
+use std::{fmt, rc::Rc};
 
 use crate::{
-    backtracker::Config,
-    board::{Board, CellType},
+    backtracker::{Config, UndoConfig},
+    board::{Axis, Board},
+    rng::Rng,
 };
 
+/// How the solver chooses which tree to place the next tent for
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TreeOrder {
+    /// Take the first tree without a tent, scanning the board row by row
+    GridOrder,
+    /// Take the tree with the fewest legal tent positions remaining (MRV)
+    #[default]
+    MostConstrained,
+}
+
+/// How the solver orders the possible tent positions of the chosen tree
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PositionOrder {
+    /// Try the positions in the order of the grid's directions, starting from West
+    Direction,
+    /// Try the positions that rule out the fewest options for other trees first (LCV)
+    #[default]
+    LeastConstraining,
+}
+
+/// The branching heuristics used when generating successor configs
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Heuristics {
+    pub tree_order: TreeOrder,
+    pub position_order: PositionOrder,
+}
+
+/// Placing a tent for a tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TentMove {
+    /// The index of the tree, in grid order
+    pub tree: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
 /// This struct holds the configuration of a step in solving a Tents and Trees Puzzle
+#[derive(Clone)]
 pub struct TentsAndTreesConfig {
     pub board: Board,
-    row: usize,
-    col: usize,
+    // The position of the tent placed to reach this config
+    last_tent: Option<(usize, usize)>,
+    heuristics: Heuristics,
+    // The positions of all trees on the board, shared between every config of a search
+    trees: Rc<[(usize, usize)]>,
+    // The indices of the trees in the order to consider them, which is grid order unless the
+    // config has been randomised
+    tree_order: Rc<[usize]>,
+    // Whether each tree in `trees` has been given its own tent
+    paired: Vec<bool>,
+    // The positions of the tents given in the puzzle, which are paired with trees by the search
+    // instead of being placed
+    given_tents: Rc<[(usize, usize)]>,
+    // Whether each tent in `given_tents` has been paired with a tree
+    claimed: Vec<bool>,
+    // The number of cells marked as empty when the last tent was placed
+    propagated: usize,
+    // What is needed to undo each move made in place, most recent last
+    undo_log: Vec<UndoEntry>,
+}
+
+/// The state replaced by a move made in place, to be restored when the move is undone
+#[derive(Clone)]
+struct UndoEntry {
+    checkpoint: usize,
+    tree: usize,
+    claimed: Option<usize>,
+    last_tent: Option<(usize, usize)>,
+    propagated: usize,
 }
 
 impl TentsAndTreesConfig {
     pub fn new(board: &Board) -> Self {
+        Self::with_heuristics(board, Heuristics::default())
+    }
+
+    pub fn with_heuristics(board: &Board, heuristics: Heuristics) -> Self {
+        let trees: Rc<[(usize, usize)]> = board
+            .get_tree_positions()
+            .into_iter()
+            .map(|(row, col, _)| (row, col))
+            .collect();
+        let given_tents: Rc<[(usize, usize)]> = board.get_tent_positions().into();
         TentsAndTreesConfig {
             board: board.clone(),
-            row: 0,
-            col: 0,
+            last_tent: None,
+            heuristics,
+            paired: vec![false; trees.len()],
+            tree_order: (0..trees.len()).collect(),
+            trees,
+            claimed: vec![false; given_tents.len()],
+            given_tents,
+            propagated: 0,
+            undo_log: Vec::new(),
         }
     }
-    fn from(old_config: &TentsAndTreesConfig, row: usize, col: usize) -> TentsAndTreesConfig {
+
+    fn from(
+        old_config: &TentsAndTreesConfig,
+        tree_idx: usize,
+        row: usize,
+        col: usize,
+    ) -> TentsAndTreesConfig {
         let mut new_board = old_config.board.clone();
-        new_board.board[row][col] = CellType::Tent;
+        let mut claimed = old_config.claimed.clone();
+        let propagated = match old_config.given_tent(row, col) {
+            Some(given_idx) => {
+                claimed[given_idx] = true;
+                0
+            }
+            None => new_board.place_tent(row, col),
+        };
+        let mut paired = old_config.paired.clone();
+        paired[tree_idx] = true;
         TentsAndTreesConfig {
             board: new_board,
-            row,
-            col,
+            last_tent: Some((row, col)),
+            heuristics: old_config.heuristics,
+            trees: Rc::clone(&old_config.trees),
+            tree_order: Rc::clone(&old_config.tree_order),
+            paired,
+            given_tents: Rc::clone(&old_config.given_tents),
+            claimed,
+            propagated,
+            undo_log: Vec::new(),
         }
     }
-}
 
-impl Config for TentsAndTreesConfig {
-    fn successors(&self) -> Vec<Self> {
-        let mut successors = Vec::new();
-        // Loop through all the trees on board, and position a tent for each.
-        // Step 1, get a vec of all the tent positions
-        let tree_positions = self.board.get_tree_positions();
-        for (tree_row_idx, tree_col_idx, possible_directions) in tree_positions {
-            if !self
-                .board
-                .does_surrounding_have_tent(tree_row_idx, tree_col_idx)
-            {
-                for direction in possible_directions.iter() {
-                    match self.board.get_possible_tent_position(
-                        tree_row_idx,
-                        tree_col_idx,
-                        direction,
-                    ) {
-                        Ok((tent_row_idx, tent_col_idx)) => {
-                            if self.board.is_valid_peek(tent_row_idx, tent_col_idx) {
-                                successors.push(TentsAndTreesConfig::from(
-                                    self,
-                                    tent_row_idx,
-                                    tent_col_idx,
-                                ));
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("{}", e);
-                            continue;
-                        }
+    /// Get the position of the tent placed to reach this config, or `None` for the initial config
+    pub fn last_tent(&self) -> Option<(usize, usize)> {
+        self.last_tent
+    }
+
+    /// Get the index of the given tent at a position, if it is one
+    fn given_tent(&self, row: usize, col: usize) -> Option<usize> {
+        self.given_tents.iter().position(|tent| *tent == (row, col))
+    }
+
+    /// Check if a given tent is next to the given tree
+    fn is_next_to_tree(&self, given_idx: usize, tree_idx: usize) -> bool {
+        let (tree_row, tree_col) = self.trees[tree_idx];
+        self.board.rules.grid.directions().iter().any(|direction| {
+            self.board.neighbour(tree_row, tree_col, direction) == Some(self.given_tents[given_idx])
+        })
+    }
+
+    /// Get the positions where a tent could legally be placed for the given tree, including the
+    /// given tents next to it that are not yet paired
+    fn tent_positions(&self, tree_idx: usize) -> Vec<(usize, usize)> {
+        let (tree_row, tree_col) = self.trees[tree_idx];
+        let mut positions: Vec<(usize, usize)> = (0..self.given_tents.len())
+            .filter(|given_idx| {
+                !self.claimed[*given_idx] && self.is_next_to_tree(*given_idx, tree_idx)
+            })
+            .map(|given_idx| self.given_tents[given_idx])
+            .collect();
+        let directions = self.board.get_possible_tent_directions(tree_row, tree_col);
+        let candidates = directions.iter().filter_map(|direction| {
+            self.board
+                .get_possible_tent_position(tree_row, tree_col, direction)
+                .ok()
+        });
+        for (tent_row, tent_col) in candidates {
+            if self.board.is_valid_peek(tent_row, tent_col) {
+                positions.push((tent_row, tent_col));
+            }
+        }
+        positions
+    }
+
+    /// Choose the tree to branch on, along with its legal tent positions. Returns `None` if every
+    /// tree already has a tent.
+    fn choose_tree(&self) -> Option<(usize, Vec<(usize, usize)>)> {
+        let mut unpaired = self
+            .tree_order
+            .iter()
+            .copied()
+            .filter(|idx| !self.paired[*idx]);
+        match self.heuristics.tree_order {
+            TreeOrder::GridOrder => unpaired
+                .next()
+                .map(|tree_idx| (tree_idx, self.tent_positions(tree_idx))),
+            TreeOrder::MostConstrained => {
+                let mut best: Option<(usize, Vec<(usize, usize)>)> = None;
+                for tree_idx in unpaired {
+                    let positions = self.tent_positions(tree_idx);
+                    // A tree with nowhere left for its tent means this config is a dead end
+                    if positions.is_empty() {
+                        return Some((tree_idx, positions));
+                    }
+                    if best
+                        .as_ref()
+                        .is_none_or(|(_, best_positions)| positions.len() < best_positions.len())
+                    {
+                        best = Some((tree_idx, positions));
                     }
                 }
+                best
             }
         }
+    }
+
+    /// Count how many tent positions of the other unpaired trees would be ruled out by placing a
+    /// tent at the given position
+    fn constraint_count(&self, tree_idx: usize, row: usize, col: usize) -> usize {
+        // The lines through the tent that its placement would fill, ruling out the rest of them
+        let filled: Vec<(Axis, usize)> = self
+            .board
+            .axes()
+            .filter_map(|axis| Some((axis, self.board.line(axis, row, col)?)))
+            .filter(|(axis, line)| {
+                self.board
+                    .clue(*axis, *line)
+                    .is_some_and(|clue| self.board.line_counts(*axis, *line).tents + 1 == clue)
+            })
+            .collect();
+        (0..self.trees.len())
+            .filter(|idx| *idx != tree_idx && !self.paired[*idx])
+            .flat_map(|idx| self.tent_positions(idx))
+            .filter(|(r, c)| {
+                (*r == row && *c == col)
+                    || self
+                        .board
+                        .tent_neighbours(row, col)
+                        .any(|cell| cell == (*r, *c))
+                    || filled
+                        .iter()
+                        .any(|(axis, line)| self.board.line(*axis, *r, *c) == Some(*line))
+            })
+            .count()
+    }
+}
+
+impl Config for TentsAndTreesConfig {
+    type Move = TentMove;
+
+    fn moves(&self) -> Vec<TentMove> {
+        // Place a tent for a single tree: every solution gives that tree one of these positions,
+        // so branching on any other tree as well would only revisit the same boards
+        let Some((tree_idx, mut positions)) = self.choose_tree() else {
+            return Vec::new();
+        };
+        if self.heuristics.position_order == PositionOrder::LeastConstraining {
+            positions.sort_by_cached_key(|(row, col)| self.constraint_count(tree_idx, *row, *col));
+        }
+
+        positions
+            .into_iter()
+            .map(|(row, col)| TentMove {
+                tree: tree_idx,
+                row,
+                col,
+            })
+            .collect()
+    }
 
-        successors
+    fn apply(&self, mv: &TentMove) -> Self {
+        TentsAndTreesConfig::from(self, mv.tree, mv.row, mv.col)
     }
 
     /// Check if the config is valid. This assumes that other tent locations are valid, and only
     /// checks the most recently added tent to see if it conflicts with other tents.
     fn is_valid(&self) -> bool {
-        // Find the minimum and maximum row and column values based on the tent position to check
-        // and the size bounds of the board.
-        // We will check the maximum of 8 surrounding cells to see if the contain a tent. If so, return false
-        let row_min = max(0i32, self.row as i32 - 1) as usize;
-        let row_max = min(self.row + 1, self.board.row_count - 1);
-        let col_min = max(0i32, self.col as i32 - 1) as usize;
-        let col_max = min(self.col + 1, self.board.col_count - 1);
-        for r in row_min..=row_max {
-            for c in col_min..=col_max {
-                // If we are in the position of the last added tent, then don't check
-                if r == self.row && c == self.col {
-                    continue;
-                }
-                if self.board.get_celltype(r, c) == CellType::Tent {
-                    return false;
-                }
-            }
-        }
-        // Check the row and column clues to make sure that we haven't added too many tents
-        if let Some(row_clue) = self.board.row_clues[self.row].get_clue() {
-            let row_count = self.board.board[self.row]
-                .iter()
-                .filter(|x| *x == &CellType::Tent)
-                .count();
-            if row_count > row_clue {
-                return false;
-            }
-        }
-        if let Some(col_clue) = self.board.col_clues[self.col].get_clue() {
-            let col_count = self
-                .board
-                .get_column(self.col)
-                .iter()
-                .filter(|x| *x == &CellType::Tent)
-                .count();
-            if col_count > col_clue {
-                return false;
-            }
+        let Some((row, col)) = self.last_tent else {
+            // No tents have been placed by the search yet
+            return true;
+        };
+        // Check the surrounding cells to see if they contain a tent. If so, return false
+        if self.board.has_touching_tent(row, col) {
+            return false;
         }
-        // No surrounding tents, so this config is valid
-        true
+        // Check the clues of the lines through the tent to make sure that we haven't added too
+        // many tents
+        let lines_valid = self.board.axes().all(|axis| {
+            self.board.line(axis, row, col).is_none_or(|line| {
+                self.board
+                    .clue(axis, line)
+                    .is_none_or(|clue| self.board.line_counts(axis, line).tents <= clue)
+            })
+        });
+        // Every given tent still needs a tree of its own from the trees that are left
+        lines_valid
+            && (0..self.given_tents.len()).all(|given_idx| {
+                self.claimed[given_idx]
+                    || (0..self.trees.len()).any(|tree_idx| {
+                        !self.paired[tree_idx] && self.is_next_to_tree(given_idx, tree_idx)
+                    })
+            })
     }
 
     fn is_goal(&self) -> bool {
-        if !self.is_valid() {
+        if !self.is_valid() || self.paired.contains(&false) || self.claimed.contains(&false) {
             return false;
         }
         self.board.is_complete()
     }
+
+    /// The same board can be reached with its tents paired to different trees, which leaves
+    /// different trees still to place, so the paired trees and given tents are hashed along with
+    /// the board.
+    fn state_key(&self) -> Option<u64> {
+        Some(
+            self.board.zobrist_hash()
+                ^ set_hash(&self.paired).rotate_left(17)
+                ^ set_hash(&self.claimed).rotate_left(41),
+        )
+    }
+
+    fn propagations(&self) -> usize {
+        self.propagated
+    }
+
+    /// Shuffle the order the trees are considered in. This is the branching order with
+    /// `TreeOrder::GridOrder`, and breaks ties between equally constrained trees otherwise.
+    fn randomise(&mut self, rng: &mut Rng) {
+        let mut tree_order: Vec<usize> = (0..self.trees.len()).collect();
+        rng.shuffle(&mut tree_order);
+        self.tree_order = tree_order.into();
+    }
+}
+
+impl UndoConfig for TentsAndTreesConfig {
+    fn apply_mut(&mut self, mv: &TentMove) {
+        self.board.enable_journal();
+        let claimed = self.given_tent(mv.row, mv.col);
+        self.undo_log.push(UndoEntry {
+            checkpoint: self.board.checkpoint(),
+            tree: mv.tree,
+            claimed,
+            last_tent: self.last_tent,
+            propagated: self.propagated,
+        });
+        self.propagated = match claimed {
+            Some(given_idx) => {
+                self.claimed[given_idx] = true;
+                0
+            }
+            None => self.board.place_tent(mv.row, mv.col),
+        };
+        self.paired[mv.tree] = true;
+        self.last_tent = Some((mv.row, mv.col));
+    }
+
+    fn undo(&mut self) {
+        if let Some(entry) = self.undo_log.pop() {
+            self.board.rollback(entry.checkpoint);
+            self.paired[entry.tree] = false;
+            if let Some(given_idx) = entry.claimed {
+                self.claimed[given_idx] = false;
+            }
+            self.last_tent = entry.last_tent;
+            self.propagated = entry.propagated;
+        }
+    }
+}
+
+/// Hash the set of indices that are flagged
+fn set_hash(flags: &[bool]) -> u64 {
+    flags
+        .iter()
+        .enumerate()
+        .filter(|(_, flag)| **flag)
+        .fold(0u64, |hash, (idx, _)| {
+            hash ^ (idx as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        })
 }
 
 impl fmt::Display for TentsAndTreesConfig {
@@ -131,3 +378,280 @@ impl fmt::Display for TentsAndTreesConfig {
         write!(f, "{}", self.board)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backtracker::{self, Outcome, SearchLimits},
+        board::CellType,
+        parser, verify,
+    };
+
+    #[test]
+    fn every_heuristic_finds_the_solution() {
+        let mut board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        board.set_mandatory_empty();
+        for tree_order in [TreeOrder::GridOrder, TreeOrder::MostConstrained] {
+            for position_order in [PositionOrder::Direction, PositionOrder::LeastConstraining] {
+                let heuristics = Heuristics {
+                    tree_order,
+                    position_order,
+                };
+                let config = TentsAndTreesConfig::with_heuristics(&board, heuristics);
+                match backtracker::solve(config, &SearchLimits::default()).outcome {
+                    Outcome::Solved(solution) => {
+                        assert!(solution.board.is_complete(), "{:?}", heuristics)
+                    }
+                    _ => panic!("example1 has a solution"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn most_constrained_tree_expands_fewer_nodes() {
+        let board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let nodes_expanded = |tree_order| {
+            let heuristics = Heuristics {
+                tree_order,
+                position_order: PositionOrder::Direction,
+            };
+            let config = TentsAndTreesConfig::with_heuristics(&board, heuristics);
+            let result = backtracker::solve(config, &SearchLimits::default());
+            assert!(matches!(result.outcome, Outcome::Solved(_)));
+            result.report.nodes_expanded
+        };
+        assert!(nodes_expanded(TreeOrder::MostConstrained) < nodes_expanded(TreeOrder::GridOrder));
+    }
+
+    #[test]
+    fn in_place_search_matches_cloning_search() {
+        let mut board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        board.set_mandatory_empty();
+        let cloned = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        let in_place =
+            backtracker::solve_in_place(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        assert_eq!(cloned.report.nodes_expanded, in_place.report.nodes_expanded);
+        assert_eq!(cloned.report.backtracks, in_place.report.backtracks);
+        assert_eq!(cloned.report.propagations, in_place.report.propagations);
+        match (cloned.outcome, in_place.outcome) {
+            (Outcome::Solved(cloned), Outcome::Solved(in_place)) => {
+                assert_eq!(cloned.board, in_place.board)
+            }
+            _ => panic!("example1 has a solution"),
+        }
+    }
+
+    #[test]
+    fn undo_restores_the_board() {
+        let board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let mut config = TentsAndTreesConfig::new(&board);
+        let first_key = config.state_key();
+        let mv = config.moves()[0];
+        config.apply_mut(&mv);
+        let second_key = config.state_key();
+        let next = config.moves()[0];
+        config.apply_mut(&next);
+        assert_eq!(
+            config.board.get_celltype(next.row, next.col),
+            CellType::Tent
+        );
+
+        config.undo();
+        assert_eq!(config.state_key(), second_key);
+        assert_eq!(config.last_tent(), Some((mv.row, mv.col)));
+        config.undo();
+        assert_eq!(config.state_key(), first_key);
+        assert_eq!(config.board, board);
+        assert_eq!(config.last_tent(), None);
+    }
+
+    #[test]
+    fn seeded_search_is_reproducible() {
+        let mut board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        board.set_mandatory_empty();
+        let heuristics = Heuristics {
+            tree_order: TreeOrder::GridOrder,
+            position_order: PositionOrder::Direction,
+        };
+        let config = TentsAndTreesConfig::with_heuristics(&board, heuristics);
+        let limits = SearchLimits::default();
+        let first = backtracker::solve_shuffled(config.clone(), &limits, 5);
+        let second = backtracker::solve_shuffled(config.clone(), &limits, 5);
+        assert_eq!(first.report.nodes_expanded, second.report.nodes_expanded);
+        assert!(
+            matches!(first.outcome, Outcome::Solved(ref solution) if solution.board.is_complete())
+        );
+
+        let policy = backtracker::RestartPolicy {
+            initial_nodes: 2,
+            growth: 2,
+            max_restarts: 10,
+        };
+        let first = backtracker::solve_with_restarts(config.clone(), &limits, 9, &policy);
+        let second = backtracker::solve_with_restarts(config, &limits, 9, &policy);
+        assert!(first.report.restarts > 0);
+        assert_eq!(first.report.restarts, second.report.restarts);
+        assert_eq!(first.report.nodes_expanded, second.report.nodes_expanded);
+        match (first.outcome, second.outcome) {
+            (Outcome::Solved(first), Outcome::Solved(second)) => {
+                assert!(first.board.is_complete());
+                assert_eq!(first.board, second.board);
+            }
+            _ => panic!("example1 has a solution"),
+        }
+    }
+
+    #[test]
+    fn failed_boards_are_recorded() {
+        let board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let heuristics = Heuristics {
+            tree_order: TreeOrder::GridOrder,
+            position_order: PositionOrder::Direction,
+        };
+        let config = TentsAndTreesConfig::with_heuristics(&board, heuristics);
+        let mut table = backtracker::TranspositionTable::default();
+        let outcome =
+            backtracker::solve_with_table(config, &SearchLimits::default(), &mut table).outcome;
+        assert!(matches!(outcome, Outcome::Solved(solution) if solution.board.is_complete()));
+        assert!(table.stats().stores > 0);
+    }
+
+    #[test]
+    fn diagonal_rule_changes_the_solution() {
+        let mut board = parser::get_board_from_file("examples/example_diagonal.txt").unwrap();
+        board.set_mandatory_empty();
+        let result = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        assert!(
+            matches!(result.outcome, Outcome::Solved(ref solution) if solution.board.is_complete())
+        );
+
+        board.rules.diagonal_tents_allowed = false;
+        let result = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        assert!(matches!(result.outcome, Outcome::Unsolvable));
+    }
+
+    #[test]
+    fn wrapped_edges_change_the_solution() {
+        let mut board = parser::get_board_from_file("examples/example_wrap.txt").unwrap();
+        board.set_mandatory_empty();
+        let result = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        let Outcome::Solved(solution) = result.outcome else {
+            panic!("example_wrap has a solution when the edges wrap");
+        };
+        assert_eq!(solution.board.get_celltype(1, 3), CellType::Tent);
+        assert_eq!(solution.board.get_celltype(3, 1), CellType::Tent);
+
+        let mut board = parser::get_board_from_file("examples/example_wrap.txt").unwrap();
+        board.rules.wrap_horizontal = false;
+        board.rules.wrap_vertical = false;
+        board.set_mandatory_empty();
+        let result = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        assert!(matches!(result.outcome, Outcome::Unsolvable));
+    }
+
+    #[test]
+    fn hex_board_uses_three_clue_axes() {
+        let mut board = parser::get_board_from_file("examples/example_hex.txt").unwrap();
+        board.set_mandatory_empty();
+        let result = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        let Outcome::Solved(solution) = result.outcome else {
+            panic!("example_hex has a solution");
+        };
+        for (row, col) in [(0, 0), (0, 3), (1, 1), (3, 1)] {
+            assert_eq!(solution.board.get_celltype(row, col), CellType::Tent);
+        }
+    }
+
+    #[test]
+    fn region_clues_are_enforced() {
+        let mut board = parser::get_board_from_file("examples/example_regions.txt").unwrap();
+        board.set_mandatory_empty();
+        let result = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        let Outcome::Solved(solution) = result.outcome else {
+            panic!("example_regions has a solution");
+        };
+        for (row, col) in [(0, 1), (1, 4), (2, 0), (2, 2), (4, 3)] {
+            assert_eq!(solution.board.get_celltype(row, col), CellType::Tent);
+        }
+        for region in 0..5 {
+            assert_eq!(solution.board.line_counts(Axis::Region, region).tents, 1);
+        }
+    }
+
+    #[test]
+    fn given_tents_are_paired_with_trees() {
+        let mut expected = parser::get_board_from_file("examples/example1.txt").unwrap();
+        expected.set_mandatory_empty();
+        let Outcome::Solved(expected) = backtracker::solve(
+            TentsAndTreesConfig::new(&expected),
+            &SearchLimits::default(),
+        )
+        .outcome
+        else {
+            panic!("example1 has a solution");
+        };
+
+        let mut board = parser::get_board_from_file("examples/example_given.txt").unwrap();
+        board.verify_given_tents().unwrap();
+        board.set_mandatory_empty();
+        let config = TentsAndTreesConfig::new(&board);
+        for result in [
+            backtracker::solve(config.clone(), &SearchLimits::default()),
+            backtracker::solve_in_place(config, &SearchLimits::default()),
+        ] {
+            let Outcome::Solved(solution) = result.outcome else {
+                panic!("example_given has a solution");
+            };
+            assert_eq!(solution.board, expected.board);
+            assert!(!solution.claimed.contains(&false));
+        }
+    }
+
+    #[test]
+    fn rectangular_boards_are_solved() {
+        for file_path in ["examples/example_5x8.txt", "examples/example_12x7.txt"] {
+            let puzzle = parser::get_board_from_file(file_path).unwrap();
+            let mut board = puzzle.clone();
+            board.set_mandatory_empty();
+            let config = TentsAndTreesConfig::new(&board);
+            for result in [
+                backtracker::solve(config.clone(), &SearchLimits::default()),
+                backtracker::solve_in_place(config, &SearchLimits::default()),
+            ] {
+                let Outcome::Solved(solution) = result.outcome else {
+                    panic!("{} has a solution", file_path);
+                };
+                assert_eq!(verify::verify(&puzzle, &solution.board), []);
+            }
+        }
+    }
+
+    #[test]
+    fn blocked_cells_stay_blocked() {
+        let board = parser::get_board_from_file("examples/example_blocked.txt").unwrap();
+        let result = backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default());
+        let Outcome::Solved(solution) = result.outcome else {
+            panic!("example_blocked has a solution");
+        };
+        assert!(solution.board.is_complete());
+        for (row, col) in [(0, 0), (0, 4), (4, 0), (4, 4)] {
+            assert_eq!(solution.board.get_celltype(row, col), CellType::Blocked);
+        }
+    }
+
+    #[test]
+    fn tree_without_room_has_no_successors() {
+        let board = Board::new(
+            vec![
+                vec![CellType::Tree, CellType::Empty],
+                vec![CellType::Empty, CellType::Unknown],
+            ],
+            vec![Some(0), Some(1)],
+            vec![Some(0), Some(1)],
+        );
+        let config = TentsAndTreesConfig::new(&board);
+        assert!(config.moves().is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    io::{self, BufReader, Read, Write},
+    mem::MaybeUninit,
+    os::{
+        fd::AsRawFd,
+        raw::{c_int, c_uchar},
+    },
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+/// Switch to the alternate screen and hide the cursor
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+/// Show the cursor and go back to the normal screen
+const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+
+/// A key pressed by the user
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Escape,
+    Char(char),
+    /// A key or escape sequence that is not recognised
+    Other,
+}
+
+/// How long to wait for the rest of an escape sequence before taking the escape as a key press of
+/// its own
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Reads key presses, taking the bytes from the input on a thread of its own so that whether the
+/// rest of an escape sequence is coming can be told by waiting a short time for it
+pub struct Keys {
+    bytes: Receiver<io::Result<u8>>,
+}
+
+impl Keys {
+    pub fn new<R: Read + Send + 'static>(input: R) -> Self {
+        let (sender, bytes) = mpsc::channel();
+        thread::spawn(move || {
+            for byte in BufReader::new(input).bytes() {
+                let failed = byte.is_err();
+                if sender.send(byte).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Keys { bytes }
+    }
+
+    /// Wait for a single key press, decoding the escape sequences sent for the arrow keys. An
+    /// escape not followed straight away by more bytes is the escape key. Returns `None` at the
+    /// end of the input.
+    pub fn read_key(&self) -> io::Result<Option<Key>> {
+        let Ok(byte) = self.bytes.recv() else {
+            return Ok(None);
+        };
+        let key = match byte? {
+            b'\r' | b'\n' => Key::Enter,
+            0x7f | 0x08 => Key::Backspace,
+            0x1b => match self.next_in_sequence()? {
+                None => Key::Escape,
+                Some(b'[') => match self.next_in_sequence()? {
+                    Some(b'A') => Key::Up,
+                    Some(b'B') => Key::Down,
+                    Some(b'C') => Key::Right,
+                    Some(b'D') => Key::Left,
+                    _ => Key::Other,
+                },
+                Some(_) => Key::Other,
+            },
+            byte if byte.is_ascii() => Key::Char(byte as char),
+            _ => Key::Other,
+        };
+        Ok(Some(key))
+    }
+
+    /// Get the next byte of an escape sequence, or `None` if none arrives in time
+    fn next_in_sequence(&self) -> io::Result<Option<u8>> {
+        self.bytes.recv_timeout(ESCAPE_TIMEOUT).ok().transpose()
+    }
+}
+
+/// The type of the flags and speeds in the terminal settings
+#[cfg(target_os = "linux")]
+type TcFlag = std::os::raw::c_uint;
+#[cfg(target_os = "macos")]
+type TcFlag = std::os::raw::c_ulong;
+
+/// The number of control characters in the terminal settings
+#[cfg(target_os = "linux")]
+const NCCS: usize = 32;
+#[cfg(target_os = "macos")]
+const NCCS: usize = 20;
+
+/// The terminal settings, laid out as `struct termios` in the C library. The fields are only
+/// read and written by the C library.
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+struct Termios {
+    c_iflag: TcFlag,
+    c_oflag: TcFlag,
+    c_cflag: TcFlag,
+    c_lflag: TcFlag,
+    #[cfg(target_os = "linux")]
+    c_line: c_uchar,
+    c_cc: [c_uchar; NCCS],
+    c_ispeed: TcFlag,
+    c_ospeed: TcFlag,
+}
+
+/// Apply new settings straight away
+const TCSANOW: c_int = 0;
+
+extern "C" {
+    fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+    fn tcsetattr(fd: c_int, optional_actions: c_int, termios: *const Termios) -> c_int;
+    fn cfmakeraw(termios: *mut Termios);
+}
+
+/// Get the settings of the terminal connected to stdin
+fn get_termios() -> io::Result<Termios> {
+    let mut termios = MaybeUninit::<Termios>::uninit();
+    // SAFETY: `tcgetattr` fills in the whole struct when it succeeds
+    unsafe {
+        if tcgetattr(io::stdin().as_raw_fd(), termios.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(termios.assume_init())
+    }
+}
+
+/// Change the settings of the terminal connected to stdin
+fn set_termios(termios: &Termios) -> io::Result<()> {
+    // SAFETY: the pointer is to a valid struct, which is only read
+    match unsafe { tcsetattr(io::stdin().as_raw_fd(), TCSANOW, termios) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Puts the terminal into raw mode on the alternate screen, so that keys are read as soon as
+/// they are pressed and nothing is echoed. The terminal is restored when this is dropped.
+pub struct RawTerminal {
+    // The settings to restore
+    saved: Termios,
+}
+
+impl RawTerminal {
+    pub fn enable() -> io::Result<Self> {
+        let saved = get_termios()?;
+        let mut raw = saved;
+        // SAFETY: the pointer is to a valid struct
+        unsafe { cfmakeraw(&mut raw) };
+        set_termios(&raw)?;
+        let terminal = RawTerminal { saved };
+        let mut stdout = io::stdout();
+        write!(stdout, "{}", ENTER_SCREEN)?;
+        stdout.flush()?;
+        Ok(terminal)
+    }
+
+    /// Draw a frame. Raw mode does not return the cursor to the start of the line on a newline,
+    /// so every newline is sent with a carriage return.
+    pub fn draw(&self, frame: &str) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        write!(stdout, "{}", frame.replace('\n', "\r\n"))?;
+        stdout.flush()
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        // Errors are ignored, as there is nothing more that can be done to restore the terminal
+        let _ = write!(io::stdout(), "{}", LEAVE_SCREEN);
+        let _ = io::stdout().flush();
+        let _ = set_termios(&self.saved);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_decoded() {
+        let keys = Keys::new(&b"\x1b[A\x1b[Dx\r\x7f\x1b[Z"[..]);
+        let mut pressed = Vec::new();
+        while let Some(key) = keys.read_key().unwrap() {
+            pressed.push(key);
+        }
+        assert_eq!(
+            pressed,
+            [
+                Key::Up,
+                Key::Left,
+                Key::Char('x'),
+                Key::Enter,
+                Key::Backspace,
+                Key::Other
+            ]
+        );
+    }
+
+    #[test]
+    fn escape_is_not_held_back() {
+        let (reader, mut writer) = io::pipe().unwrap();
+        let keys = Keys::new(reader);
+        writer.write_all(b"\x1b").unwrap();
+        assert_eq!(keys.read_key().unwrap(), Some(Key::Escape));
+        writer.write_all(b"\x1b[Bq").unwrap();
+        assert_eq!(keys.read_key().unwrap(), Some(Key::Down));
+        assert_eq!(keys.read_key().unwrap(), Some(Key::Char('q')));
+        drop(writer);
+        assert_eq!(keys.read_key().unwrap(), None);
+    }
+}
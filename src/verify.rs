@@ -0,0 +1,368 @@
+use std::fmt::Display;
+
+use crate::{
+    board::{Axis, Board, CellType},
+    render::{self, Highlight},
+};
+
+/// A rule broken by a player's attempt at a puzzle, as found by `verify`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mistake {
+    /// The attempt is not the same size as the puzzle, so nothing else was checked
+    WrongSize {
+        rows: usize,
+        cols: usize,
+        expected_rows: usize,
+        expected_cols: usize,
+    },
+    /// A cell given in the puzzle was changed, or a cell was given something other than a tent
+    /// or grass
+    ChangedCell {
+        row: usize,
+        col: usize,
+        given: CellType,
+        found: CellType,
+    },
+    TouchingTents((usize, usize), (usize, usize)),
+    /// A line does not have the number of tents its clue asks for
+    WrongTentCount {
+        axis: Axis,
+        line: usize,
+        clue: usize,
+        tents: usize,
+    },
+    /// A tent that cannot be paired with a tree of its own
+    TentWithoutTree(usize, usize),
+    /// A tree that cannot be paired with a tent of its own
+    TreeWithoutTent(usize, usize),
+    /// A line of a partly filled in board has too few unmarked cells left to meet its clue
+    NotEnoughRoom {
+        axis: Axis,
+        line: usize,
+        clue: usize,
+        room: usize,
+    },
+}
+
+impl Mistake {
+    /// Get the cells of the attempt that are part of the mistake
+    pub fn cells(&self, puzzle: &Board, attempt: &Board) -> Vec<(usize, usize)> {
+        match *self {
+            Mistake::WrongSize { .. } | Mistake::NotEnoughRoom { .. } => Vec::new(),
+            Mistake::ChangedCell { row, col, .. }
+            | Mistake::TentWithoutTree(row, col)
+            | Mistake::TreeWithoutTent(row, col) => vec![(row, col)],
+            Mistake::TouchingTents(first, second) => vec![first, second],
+            Mistake::WrongTentCount { axis, line, .. } => puzzle
+                .line_cells(axis, line)
+                .filter(|(row, col)| attempt.get_celltype(*row, *col) == CellType::Tent)
+                .collect(),
+        }
+    }
+}
+
+impl Display for Mistake {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mistake::WrongSize {
+                rows,
+                cols,
+                expected_rows,
+                expected_cols,
+            } => write!(
+                f,
+                "The attempt is {}x{} but the puzzle is {}x{}",
+                rows, cols, expected_rows, expected_cols
+            ),
+            Mistake::ChangedCell {
+                row,
+                col,
+                given,
+                found,
+            } => write!(
+                f,
+                "The cell at ({}, {}) should be {:?} but is {:?}",
+                row, col, given, found
+            ),
+            Mistake::TouchingTents((r1, c1), (r2, c2)) => write!(
+                f,
+                "The tents at ({}, {}) and ({}, {}) touch",
+                r1, c1, r2, c2
+            ),
+            Mistake::WrongTentCount {
+                axis,
+                line,
+                clue,
+                tents,
+            } => write!(
+                f,
+                "There are {} tents in {} {} but its clue is {}",
+                tents, axis, line, clue
+            ),
+            Mistake::TentWithoutTree(r, c) => {
+                write!(f, "The tent at ({}, {}) has no tree of its own", r, c)
+            }
+            Mistake::TreeWithoutTent(r, c) => {
+                write!(f, "The tree at ({}, {}) has no tent of its own", r, c)
+            }
+            Mistake::NotEnoughRoom {
+                axis,
+                line,
+                clue,
+                room,
+            } => write!(
+                f,
+                "There is only room for {} tents in {} {} but its clue is {}",
+                room, axis, line, clue
+            ),
+        }
+    }
+}
+
+/// Check a player's attempt at a puzzle, returning every rule it breaks. The attempt must keep
+/// every cell given in the puzzle, and only add tents and grass; unmarked cells count as grass.
+/// Its tents must not touch, must meet every clue of the puzzle, and must pair up with the
+/// trees so that each tree has a tent of its own next to it.
+pub fn verify(puzzle: &Board, attempt: &Board) -> Vec<Mistake> {
+    let (rows, cols) = (puzzle.row_clues.len(), puzzle.col_clues.len());
+    let (attempt_rows, attempt_cols) = (attempt.row_clues.len(), attempt.col_clues.len());
+    if (rows, cols) != (attempt_rows, attempt_cols) {
+        return vec![Mistake::WrongSize {
+            rows: attempt_rows,
+            cols: attempt_cols,
+            expected_rows: rows,
+            expected_cols: cols,
+        }];
+    }
+
+    // Apply the attempt to the puzzle, so that it is checked with the puzzle's clues and rules
+    let mut mistakes = Vec::new();
+    let mut board = puzzle.clone();
+    for row in 0..rows {
+        for col in 0..cols {
+            let given = puzzle.get_celltype(row, col);
+            let found = attempt.get_celltype(row, col);
+            match (given, found) {
+                (CellType::Unknown, CellType::Tent | CellType::Empty | CellType::Unknown) => {
+                    board.set_celltype(row, col, found)
+                }
+                _ if given == found => {}
+                _ => mistakes.push(Mistake::ChangedCell {
+                    row,
+                    col,
+                    given,
+                    found,
+                }),
+            }
+        }
+    }
+
+    let tents = board.get_tent_positions();
+    find_touching_tents(&board, &tents, &mut mistakes);
+    for axis in board.axes() {
+        for line in 0..board.clues(axis).len() {
+            let tents = board.line_counts(axis, line).tents;
+            if let Some(clue) = board.clue(axis, line).filter(|clue| *clue != tents) {
+                mistakes.push(Mistake::WrongTentCount {
+                    axis,
+                    line,
+                    clue,
+                    tents,
+                });
+            }
+        }
+    }
+
+    let pairs = board.pair_tents(&tents);
+    for ((row, col), tree) in tents.iter().zip(&pairs) {
+        if tree.is_none() {
+            mistakes.push(Mistake::TentWithoutTree(*row, *col));
+        }
+    }
+    for (row, col, _) in board.get_tree_positions() {
+        if !pairs.contains(&Some((row, col))) {
+            mistakes.push(Mistake::TreeWithoutTent(row, col));
+        }
+    }
+    mistakes
+}
+
+/// Check a partly filled in board for rules it already breaks, whatever goes in the cells that
+/// are still unmarked: touching tents, lines with too many tents or too little room left, tents
+/// that cannot be paired with a tree, and trees with nowhere left for a tent.
+pub fn broken_rules(board: &Board) -> Vec<Mistake> {
+    let mut mistakes = Vec::new();
+    let tents = board.get_tent_positions();
+    find_touching_tents(board, &tents, &mut mistakes);
+    for axis in board.axes() {
+        for line in 0..board.clues(axis).len() {
+            let Some(clue) = board.clue(axis, line) else {
+                continue;
+            };
+            let counts = board.line_counts(axis, line);
+            if counts.tents > clue {
+                mistakes.push(Mistake::WrongTentCount {
+                    axis,
+                    line,
+                    clue,
+                    tents: counts.tents,
+                });
+            } else if counts.tents + counts.unknown < clue {
+                mistakes.push(Mistake::NotEnoughRoom {
+                    axis,
+                    line,
+                    clue,
+                    room: counts.tents + counts.unknown,
+                });
+            }
+        }
+    }
+
+    let pairs = board.pair_tents(&tents);
+    for ((row, col), tree) in tents.iter().zip(&pairs) {
+        if tree.is_none() {
+            mistakes.push(Mistake::TentWithoutTree(*row, *col));
+        }
+    }
+    for (row, col, _) in board.get_tree_positions() {
+        let has_room = board.rules.grid.directions().iter().any(|direction| {
+            board.neighbour(row, col, direction).is_some_and(|(r, c)| {
+                matches!(board.get_celltype(r, c), CellType::Tent | CellType::Unknown)
+            })
+        });
+        if !has_room {
+            mistakes.push(Mistake::TreeWithoutTent(row, col));
+        }
+    }
+    mistakes
+}
+
+fn find_touching_tents(board: &Board, tents: &[(usize, usize)], mistakes: &mut Vec<Mistake>) {
+    for (row, col) in tents {
+        for other in board.tent_neighbours(*row, *col) {
+            if board.get_celltype(other.0, other.1) == CellType::Tent && (*row, *col) < other {
+                mistakes.push(Mistake::TouchingTents((*row, *col), other));
+            }
+        }
+    }
+}
+
+/// Render the attempt with the tents the player added in green, and the cells involved in any
+/// of the mistakes in red
+pub fn render_diff(puzzle: &Board, attempt: &Board, mistakes: &[Mistake]) -> String {
+    let wrong: Vec<(usize, usize)> = mistakes
+        .iter()
+        .flat_map(|mistake| mistake.cells(puzzle, attempt))
+        .collect();
+    render::render_board(attempt, |row, col| {
+        if wrong.contains(&(row, col)) {
+            Some(Highlight::Removed)
+        } else if attempt.get_celltype(row, col) == CellType::Tent
+            && puzzle.get_celltype(row, col) != CellType::Tent
+        {
+            Some(Highlight::Added)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backtracker::{self, Outcome, SearchLimits},
+        parser,
+        solver::TentsAndTreesConfig,
+    };
+
+    #[test]
+    fn solution_from_the_solver_is_correct() {
+        let puzzle = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let mut board = puzzle.clone();
+        board.set_mandatory_empty();
+        let Outcome::Solved(solution) =
+            backtracker::solve(TentsAndTreesConfig::new(&board), &SearchLimits::default()).outcome
+        else {
+            panic!("example1 has a solution");
+        };
+        assert_eq!(verify(&puzzle, &solution.board), []);
+    }
+
+    #[test]
+    fn every_broken_rule_is_reported() {
+        let puzzle = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let attempt = parser::get_board_from_file("examples/example1_attempt.txt").unwrap();
+        assert_eq!(
+            verify(&puzzle, &attempt),
+            [
+                Mistake::TouchingTents((1, 0), (2, 0)),
+                Mistake::WrongTentCount {
+                    axis: Axis::Row,
+                    line: 0,
+                    clue: 3,
+                    tents: 2
+                },
+                Mistake::WrongTentCount {
+                    axis: Axis::Row,
+                    line: 1,
+                    clue: 1,
+                    tents: 2
+                },
+                Mistake::TentWithoutTree(1, 0),
+                Mistake::TreeWithoutTent(0, 3),
+            ]
+        );
+
+        let mut changed = attempt.clone();
+        changed.set_celltype(7, 1, CellType::Unknown);
+        assert!(verify(&puzzle, &changed).contains(&Mistake::ChangedCell {
+            row: 7,
+            col: 1,
+            given: CellType::Tree,
+            found: CellType::Unknown
+        }));
+    }
+
+    #[test]
+    fn partial_boards_report_broken_rules() {
+        let mut board = parser::get_board_from_file("examples/example1.txt").unwrap();
+        assert_eq!(broken_rules(&board), []);
+        board.set_celltype(1, 0, CellType::Tent);
+        board.set_celltype(1, 2, CellType::Tent);
+        board.set_celltype(2, 0, CellType::Tent);
+        for col in 1..8 {
+            board.set_celltype(4, col, CellType::Empty);
+        }
+        assert_eq!(
+            broken_rules(&board),
+            [
+                Mistake::TouchingTents((1, 0), (2, 0)),
+                Mistake::WrongTentCount {
+                    axis: Axis::Row,
+                    line: 1,
+                    clue: 1,
+                    tents: 2
+                },
+                Mistake::NotEnoughRoom {
+                    axis: Axis::Row,
+                    line: 4,
+                    clue: 3,
+                    room: 1
+                },
+                Mistake::TentWithoutTree(1, 0),
+                Mistake::TentWithoutTree(1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_highlights_mistakes() {
+        let puzzle = parser::get_board_from_file("examples/example1.txt").unwrap();
+        let attempt = parser::get_board_from_file("examples/example1_attempt.txt").unwrap();
+        let diff = render_diff(&puzzle, &attempt, &verify(&puzzle, &attempt));
+        let first_row = diff.lines().nth(2).unwrap();
+        assert!(first_row.starts_with(" 3 | - "));
+        assert!(first_row.contains("\x1b[30;41m X "));
+    }
+}